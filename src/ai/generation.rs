@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use anyhow::{Result, anyhow};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// How `LLMWrapper` samples a completion.
+#[derive(Debug, Clone)]
+pub struct GenerationConfig {
+    /// Upper bound on the number of sampled tokens.
+    pub max_tokens: usize,
+    /// Softmax temperature; 0 always picks the most likely token.
+    pub temperature: f64,
+    /// Keeps only the `top_k` most likely tokens.
+    pub top_k: Option<usize>,
+    /// Keeps the fewest most likely tokens whose probabilities reach `top_p`.
+    pub top_p: Option<f64>,
+    /// Drops tokens less than `min_p` times as likely as the best one.
+    pub min_p: Option<f64>,
+    /// Makes sampling reproducible; a random seed is used when `None`.
+    pub seed: Option<u64>,
+    /// The completion ends before the first of these strings.
+    pub stop: Vec<String>,
+    pub repeat_penalty: f32,
+    pub repeat_last_n: usize,
+    /// Added to the logits of the given token ids before sampling.
+    pub logit_bias: HashMap<u32, f32>,
+    /// Token ids that are never sampled.
+    pub banned_tokens: Vec<u32>,
+}
+
+impl Default for GenerationConfig {
+    fn default() -> Self {
+        Self {
+            max_tokens: 256,
+            temperature: 0.8,
+            top_k: None,
+            top_p: None,
+            min_p: None,
+            seed: None,
+            stop: Vec::new(),
+            repeat_penalty: 1.1,
+            repeat_last_n: 64,
+            logit_bias: HashMap::new(),
+            banned_tokens: Vec::new(),
+        }
+    }
+}
+
+impl GenerationConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !self.temperature.is_finite() || self.temperature < 0.0 {
+            return Err(anyhow!("temperature must be a non-negative number"));
+        }
+        if self.top_k == Some(0) {
+            return Err(anyhow!("top_k must be positive"));
+        }
+        if self.top_p.is_some_and(|p| p.is_nan() || p <= 0.0 || p > 1.0) {
+            return Err(anyhow!("top_p must be in (0, 1]"));
+        }
+        if self.min_p.is_some_and(|p| !(0.0..=1.0).contains(&p)) {
+            return Err(anyhow!("min_p must be between 0 and 1"));
+        }
+        if self.stop.iter().any(String::is_empty) {
+            return Err(anyhow!("Stop strings must not be empty"));
+        }
+        if self.repeat_penalty <= 0.0 {
+            return Err(anyhow!("repeat_penalty must be positive"));
+        }
+        Ok(())
+    }
+}
+
+/// Picks tokens from logits according to a `GenerationConfig`.
+pub struct Sampler {
+    config: GenerationConfig,
+    rng: StdRng,
+}
+
+impl Sampler {
+    pub fn new(config: &GenerationConfig) -> Self {
+        let rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Self { config: config.clone(), rng }
+    }
+
+    /// Samples a token id from `logits`, which hold one value per token.
+    pub fn sample(&mut self, logits: &[f32]) -> Result<u32> {
+        let mut logits = logits.to_vec();
+        for (token, bias) in &self.config.logit_bias {
+            if let Some(logit) = logits.get_mut(*token as usize) {
+                *logit += bias;
+            }
+        }
+        for token in &self.config.banned_tokens {
+            if let Some(logit) = logits.get_mut(*token as usize) {
+                *logit = f32::NEG_INFINITY;
+            }
+        }
+
+        let mut candidates: Vec<(u32, f32)> = logits.into_iter()
+            .enumerate()
+            .filter(|(_, logit)| logit.is_finite())
+            .map(|(token, logit)| (token as u32, logit))
+            .collect();
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let best = candidates.first().ok_or_else(|| anyhow!("No token is left to sample"))?.1;
+        if self.config.temperature == 0.0 {
+            return Ok(candidates[0].0);
+        }
+
+        // Probabilities relative to the best token, most likely first
+        let mut probabilities: Vec<(u32, f64)> = candidates.into_iter()
+            .map(|(token, logit)| (token, ((logit - best) as f64 / self.config.temperature).exp()))
+            .collect();
+        let total: f64 = probabilities.iter().map(|(_, p)| p).sum();
+        probabilities.iter_mut().for_each(|(_, p)| *p /= total);
+        if let Some(min_p) = self.config.min_p {
+            let threshold = probabilities[0].1 * min_p;
+            probabilities.retain(|(_, p)| *p >= threshold);
+        }
+        if let Some(top_k) = self.config.top_k {
+            probabilities.truncate(top_k);
+        }
+        if let Some(top_p) = self.config.top_p {
+            let mut cumulative = 0.0;
+            let kept = probabilities.iter()
+                .position(|(_, p)| {
+                    cumulative += p;
+                    cumulative >= top_p
+                })
+                .map_or(probabilities.len(), |last| last + 1);
+            probabilities.truncate(kept);
+        }
+
+        let total: f64 = probabilities.iter().map(|(_, p)| p).sum();
+        let mut target = self.rng.gen::<f64>() * total;
+        for (token, p) in &probabilities {
+            target -= p;
+            if target <= 0.0 {
+                return Ok(*token);
+            }
+        }
+        Ok(probabilities[probabilities.len() - 1].0)
+    }
+}
+
+/// Assembles streamed text and cuts it at the first stop string. Text that
+/// may still grow into a stop string is held back until it can't.
+pub struct StopStrings<'a> {
+    stop: &'a [String],
+    text: String,
+    sent: usize,
+    stopped: bool,
+}
+
+impl<'a> StopStrings<'a> {
+    pub fn new(stop: &'a [String]) -> Self {
+        Self { stop, text: String::new(), sent: 0, stopped: false }
+    }
+
+    /// Appends `delta`, returning whatever text is now safe to pass on.
+    pub fn push(&mut self, delta: &str) -> Option<String> {
+        self.text.push_str(delta);
+        let end = match self.first_stop(&self.text) {
+            Some(at) => {
+                self.text.truncate(at);
+                self.stopped = true;
+                at
+            }
+            None => self.text.len() - self.held_back(),
+        };
+        self.release(end)
+    }
+
+    /// Releases the text held back once no more will be appended.
+    pub fn finish(&mut self) -> Option<String> {
+        self.release(self.text.len())
+    }
+
+    pub fn stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Cuts `text`, e.g. the whole decoded output, at the first stop string.
+    pub fn cut<'t>(&self, text: &'t str) -> &'t str {
+        &text[..self.first_stop(text).unwrap_or(text.len())]
+    }
+
+    fn first_stop(&self, text: &str) -> Option<usize> {
+        self.stop.iter().filter_map(|stop| text.find(stop.as_str())).min()
+    }
+
+    /// Length of the longest tail of the text that begins a stop string.
+    fn held_back(&self) -> usize {
+        self.text.char_indices()
+            .map(|(start, _)| &self.text[start..])
+            .find(|tail| self.stop.iter().any(|stop| stop.starts_with(tail)))
+            .map_or(0, str::len)
+    }
+
+    fn release(&mut self, end: usize) -> Option<String> {
+        if end <= self.sent {
+            return None;
+        }
+        let released = self.text[self.sent..end].to_string();
+        self.sent = end;
+        Some(released)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sampler() {
+        let logits = [1.0, 3.0, 2.0, 0.5];
+        let greedy = GenerationConfig { temperature: 0.0, ..Default::default() };
+        assert_eq!(Sampler::new(&greedy).sample(&logits).unwrap(), 1);
+
+        let banned = GenerationConfig { temperature: 0.0, banned_tokens: vec![1], ..Default::default() };
+        assert_eq!(Sampler::new(&banned).sample(&logits).unwrap(), 2);
+        let biased = GenerationConfig { temperature: 0.0, logit_bias: HashMap::from([(3, 5.0)]), ..Default::default() };
+        assert_eq!(Sampler::new(&biased).sample(&logits).unwrap(), 3);
+
+        // top_k = 2 only ever yields the two most likely tokens
+        let top_k = GenerationConfig { top_k: Some(2), seed: Some(7), ..Default::default() };
+        let mut sampler = Sampler::new(&top_k);
+        assert!((0..100).all(|_| matches!(sampler.sample(&logits).unwrap(), 1 | 2)));
+
+        // Equal seeds give equal samples
+        let seeded = GenerationConfig { temperature: 1.5, seed: Some(42), ..Default::default() };
+        let (mut a, mut b) = (Sampler::new(&seeded), Sampler::new(&seeded));
+        let first: Vec<u32> = (0..20).map(|_| a.sample(&logits).unwrap()).collect();
+        let second: Vec<u32> = (0..20).map(|_| b.sample(&logits).unwrap()).collect();
+        assert_eq!(first, second);
+
+        assert!(GenerationConfig { top_p: Some(1.5), ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_stop_strings() {
+        let stop = vec!["\nUser:".to_string()];
+        let mut stream = StopStrings::new(&stop);
+        assert_eq!(stream.push("Hello"), Some("Hello".to_string()));
+        // A newline may begin the stop string, so it waits
+        assert_eq!(stream.push(" there\n"), Some(" there".to_string()));
+        assert_eq!(stream.push("Us"), None);
+        assert_eq!(stream.push("er: hi"), None);
+        assert!(stream.stopped());
+        assert_eq!(stream.cut("Hello there\nUser: hi"), "Hello there");
+
+        let mut stream = StopStrings::new(&stop);
+        assert_eq!(stream.push("a\n"), Some("a".to_string()));
+        assert_eq!(stream.push("b"), Some("\nb".to_string()));
+        stream.push("\nUs");
+        assert_eq!(stream.finish(), Some("\nUs".to_string()));
+        assert!(!stream.stopped());
+    }
+}
@@ -1,12 +1,12 @@
 use anyhow::{Result, Context};
 use candle_core::{Device, Tensor};
 use candle_transformers::models::quantized_llama::ModelWeights;
-use candle_transformers::generation::{LogitsProcessor, Sampling};
 use tokenizers::Tokenizer;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::cell::RefCell;
 use candle_core::quantized::{ggml_file, gguf_file};
+use crate::ai::generation::{GenerationConfig, Sampler, StopStrings};
 
 pub struct LLMWrapper {
     model: Arc<RefCell<ModelWeights>>,
@@ -65,55 +65,134 @@ impl LLMWrapper {
         })
     }
 
-    pub fn generate(&self, prompt: &str, sample_len: usize, temp: f64, repeat_penalty: f32, repeat_last_n: usize) -> Result<String> {
+    /// Looks up the id of a token in the model's vocabulary.
+    pub fn token_id(&self, token: &str) -> Option<u32> {
+        self.tokenizer.token_to_id(token)
+    }
+
+    pub fn generate(&self, prompt: &str, config: &GenerationConfig) -> Result<String> {
+        self.generate_stream(prompt, config, |_| true)
+    }
+
+    /// Like `generate`, but hands each piece of decoded text to `on_delta`
+    /// as soon as the sampled tokens spell it out completely. Returning
+    /// `false` from `on_delta` stops the generation early.
+    pub fn generate_stream<F: FnMut(&str) -> bool>(&self, prompt: &str, config: &GenerationConfig, mut on_delta: F) -> Result<String> {
+        config.validate()?;
         let tokens = self.tokenizer.encode(prompt, true).map_err(|e| anyhow::anyhow!("Tokenizer encode error: {}", e))?;
         let prompt_tokens = tokens.get_ids().to_vec();
         let mut all_tokens = vec![];
-        
-        let mut logits_processor = {
-            let sampling = if temp <= 0. {
-                Sampling::ArgMax
-            } else {
-                Sampling::All { temperature: temp }
-            };
-            LogitsProcessor::new(299792458, None, None)
+        let decode = |tokens: &[u32]| {
+            self.tokenizer.decode(tokens, true).map_err(|e| anyhow::anyhow!("Decode error: {}", e))
         };
-
-        let mut next_token = {
-            let input = Tensor::new(prompt_tokens.as_slice(), &self.device)?.unsqueeze(0)?;
-            let logits = self.model.borrow_mut().forward(&input, 0)?;
-            let logits = logits.squeeze(0)?;
-            logits_processor.sample(&logits)?
-        };
-
-        all_tokens.push(next_token);
+        let mut deltas = TextDeltas::default();
+        let mut output = StopStrings::new(&config.stop);
+        let mut sampler = Sampler::new(config);
 
         let eos_token = self.tokenizer.token_to_id("</s>").ok_or_else(|| anyhow::anyhow!("EOS token not found"))?;
+        let mut next_token = eos_token;
+        let mut interrupted = false;
+        // Held for the whole generation: `on_delta` may run code that tries
+        // to generate again, e.g. an `llm_query` from an `on_token` callback
+        let mut model = self.model.try_borrow_mut()
+            .map_err(|_| anyhow::anyhow!("The model is already generating; it can't be used from a token callback"))?;
 
-        for index in 0..sample_len {
-            let input = Tensor::new(&[next_token], &self.device)?.unsqueeze(0)?;
-            let logits = self.model.borrow_mut().forward(&input, prompt_tokens.len() + index)?;
+        for index in 0..config.max_tokens {
+            // The whole prompt goes through the model first, then one token at a time
+            let (input, position) = if index == 0 {
+                (prompt_tokens.as_slice(), 0)
+            } else {
+                (std::slice::from_ref(&next_token), prompt_tokens.len() + index - 1)
+            };
+            let input = Tensor::new(input, &self.device)?.unsqueeze(0)?;
+            let logits = model.forward(&input, position)?;
             let logits = logits.squeeze(0)?;
-            let logits = if repeat_penalty == 1. {
+            let logits = if config.repeat_penalty == 1. {
                 logits
             } else {
-                let start_at = all_tokens.len().saturating_sub(repeat_last_n);
+                let start_at = all_tokens.len().saturating_sub(config.repeat_last_n);
                 candle_transformers::utils::apply_repeat_penalty(
                     &logits,
-                    repeat_penalty,
+                    config.repeat_penalty,
                     &all_tokens[start_at..],
                 )?
             };
-            next_token = logits_processor.sample(&logits)?;
-            all_tokens.push(next_token);
+            next_token = sampler.sample(&logits.to_vec1::<f32>()?)?;
             if next_token == eos_token {
                 break;
             }
+            all_tokens.push(next_token);
+            if let Some(delta) = deltas.push(next_token, decode)? {
+                let keep_going = output.push(&delta).map_or(true, |released| on_delta(&released));
+                if output.stopped() || !keep_going {
+                    interrupted = true;
+                    break;
+                }
+            }
+        }
+        if !interrupted {
+            if let Some(delta) = deltas.finish(decode)? {
+                if let Some(released) = output.push(&delta) {
+                    on_delta(&released);
+                }
+            }
+            if let Some(released) = output.finish() {
+                on_delta(&released);
+            }
         }
 
-        let output = self.tokenizer.decode(&all_tokens, true).map_err(|e| anyhow::anyhow!("Decode error: {}", e))?;
+        // The deltas may split text differently from a single decode, so the
+        // result comes from all the tokens at once
+        Ok(output.cut(&decode(&all_tokens)?).to_string())
+    }
+}
 
-        Ok(output)
+/// Turns a growing token sequence into text deltas. A token can end partway
+/// through a multi-byte character, which decodes to U+FFFD until later
+/// tokens complete it, so such text is held back. Only a short window of
+/// tokens is decoded each time; it starts one emitted token early so that
+/// tokenizers which drop a leading space still see the space in context.
+#[derive(Default)]
+struct TextDeltas {
+    tokens: Vec<u32>,
+    window_start: usize,
+    emitted: usize,
+}
+
+impl TextDeltas {
+    fn push(&mut self, token: u32, decode: impl Fn(&[u32]) -> Result<String>) -> Result<Option<String>> {
+        self.tokens.push(token);
+        let delta = self.pending(&decode)?;
+        match delta {
+            Some(ref delta) if !delta.ends_with('\u{FFFD}') => {
+                self.window_start = self.emitted;
+                self.emitted = self.tokens.len();
+                Ok(Some(delta.clone()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Flushes whatever text is still held back once generation ends.
+    fn finish(&mut self, decode: impl Fn(&[u32]) -> Result<String>) -> Result<Option<String>> {
+        let delta = self.pending(&decode)?;
+        self.emitted = self.tokens.len();
+        Ok(delta)
+    }
+
+    fn pending(&mut self, decode: &impl Fn(&[u32]) -> Result<String>) -> Result<Option<String>> {
+        let seen = decode(&self.tokens[self.window_start..self.emitted])?;
+        let text = decode(&self.tokens[self.window_start..])?;
+        let delta = match text.strip_prefix(seen.as_str()) {
+            Some(delta) => delta.to_string(),
+            // The new tokens changed how the emitted ones decode; start the
+            // window at the new tokens instead of losing their text
+            None => {
+                self.window_start = self.emitted;
+                decode(&self.tokens[self.emitted..])?
+            }
+        };
+        Ok(Some(delta).filter(|delta| !delta.is_empty()))
     }
 }
 
@@ -127,4 +206,47 @@ fn format_size(size_in_bytes: usize) -> String {
     } else {
         format!("{:.2}GB", size_in_bytes as f64 / 1e9)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_deltas() {
+        // One byte per token, so multi-byte characters span several tokens
+        let decode = |tokens: &[u32]| -> Result<String> {
+            let bytes: Vec<u8> = tokens.iter().map(|t| *t as u8).collect();
+            Ok(String::from_utf8_lossy(&bytes).into_owned())
+        };
+        let mut deltas = TextDeltas::default();
+        let mut streamed = Vec::new();
+        for byte in "hé €".bytes() {
+            streamed.push(deltas.push(byte as u32, decode).unwrap());
+        }
+        assert_eq!(streamed, vec![
+            Some("h".to_string()), None, Some("é".to_string()),
+            Some(" ".to_string()), None, None, Some("€".to_string()),
+        ]);
+
+        // A character cut off by the end of generation is still flushed
+        deltas.push(0xe2, decode).unwrap();
+        assert_eq!(deltas.finish(decode).unwrap(), Some("\u{FFFD}".to_string()));
+    }
+
+    #[test]
+    fn test_text_deltas_context_change() {
+        // Token 2 turns a preceding token 1 into "X", so the emitted "a" is
+        // no longer a prefix of the window
+        let decode = |tokens: &[u32]| -> Result<String> {
+            Ok(match tokens {
+                [1, 2, ..] => "X".to_string() + &"b".repeat(tokens.len() - 2),
+                _ => tokens.iter().map(|t| if *t == 1 { "a" } else { "b" }).collect(),
+            })
+        };
+        let mut deltas = TextDeltas::default();
+        assert_eq!(deltas.push(1, decode).unwrap(), Some("a".to_string()));
+        assert_eq!(deltas.push(2, decode).unwrap(), Some("b".to_string()));
+        assert_eq!(deltas.push(2, decode).unwrap(), Some("b".to_string()));
+    }
+}
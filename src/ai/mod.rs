@@ -1,5 +1,7 @@
 mod llm;
 mod embedding;
+mod generation;
 
 pub use llm::LLMWrapper;
-pub use embedding::EmbeddingWrapper;
\ No newline at end of file
+pub use embedding::EmbeddingWrapper;
+pub use generation::GenerationConfig;
\ No newline at end of file
@@ -0,0 +1,97 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use crate::core::{NamespaceManager, VectorSearchOptions};
+use crate::vector::io::read_vectors;
+use anyhow::{Result, anyhow};
+use rand::seq::SliceRandom;
+
+pub struct RecallOptions {
+    pub namespace: String,
+    pub field: Option<String>,
+    pub k: usize,
+    /// Number of stored vectors to sample as queries when `queries` is unset.
+    pub samples: usize,
+    /// A `.fvecs` or `.npy` query set.
+    pub queries: Option<PathBuf>,
+    /// `expansion_search` values to compare; the index default when empty.
+    pub expansions: Vec<usize>,
+}
+
+/// Compares approximate results with exact search over the same queries and
+/// prints recall@k and latency percentiles for each expansion setting.
+pub fn recall(manager: &NamespaceManager, options: &RecallOptions) -> Result<()> {
+    let ns = manager.get_namespace(&options.namespace)?;
+    let field = ns.vector_field(options.field.as_deref())?;
+
+    let queries = match &options.queries {
+        Some(path) => read_vectors(path)?,
+        None => {
+            let ids = ns.keys.ids()?;
+            let mut queries = Vec::new();
+            for id in ids.choose_multiple(&mut rand::thread_rng(), options.samples) {
+                queries.extend(ns.source_vectors(options.field.as_deref(), *id)?.into_iter().take(1));
+            }
+            queries
+        }
+    };
+    if queries.is_empty() {
+        return Err(anyhow!("No queries to run; namespace '{}' has no vectors", options.namespace));
+    }
+
+    let exact_options = VectorSearchOptions { field: options.field.clone(), exact: true, ..Default::default() };
+    let mut truth = Vec::with_capacity(queries.len());
+    for query in &queries {
+        let ids: HashSet<u64> = ns.search_vectors(query, options.k, &exact_options)?
+            .into_iter()
+            .map(|hit| hit.id)
+            .collect();
+        truth.push(ids);
+    }
+
+    println!("{} queries, k = {}, {} vectors", queries.len(), options.k, field.index.size());
+    println!("{:>10} {:>10} {:>10} {:>10} {:>10}", "expansion", "recall", "p50 ms", "p95 ms", "p99 ms");
+    let expansions: Vec<Option<usize>> = if options.expansions.is_empty() {
+        vec![None]
+    } else {
+        options.expansions.iter().copied().map(Some).collect()
+    };
+    for expansion in expansions {
+        let search_options = VectorSearchOptions {
+            field: options.field.clone(),
+            expansion_search: expansion,
+            ..Default::default()
+        };
+        let mut latencies = Vec::with_capacity(queries.len());
+        let mut recall_sum = 0.0;
+        for (query, expected) in queries.iter().zip(&truth) {
+            let started = Instant::now();
+            let hits = ns.search_vectors(query, options.k, &search_options)?;
+            latencies.push(started.elapsed());
+            if !expected.is_empty() {
+                let found = hits.iter().filter(|hit| expected.contains(&hit.id)).count();
+                recall_sum += found as f64 / expected.len() as f64;
+            }
+        }
+        latencies.sort();
+        let label = expansion.map_or_else(|| "default".to_string(), |e| e.to_string());
+        println!(
+            "{:>10} {:>10.4} {:>10.3} {:>10.3} {:>10.3}",
+            label,
+            recall_sum / queries.len() as f64,
+            millis(percentile(&latencies, 0.50)),
+            millis(percentile(&latencies, 0.95)),
+            millis(percentile(&latencies, 0.99)),
+        );
+    }
+    Ok(())
+}
+
+fn percentile(sorted: &[Duration], quantile: f64) -> Duration {
+    let rank = ((sorted.len() as f64 * quantile).ceil() as usize).clamp(1, sorted.len());
+    sorted[rank - 1]
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
@@ -1,5 +1,7 @@
 use std::io::{self, Write};
-use crate::query::QueryExecutor;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::query::{QueryExecutor, TokenSink};
 use anyhow::Result;
 
 pub async fn run(query_executor: QueryExecutor) -> Result<()> {
@@ -25,12 +27,27 @@ pub async fn run(query_executor: QueryExecutor) -> Result<()> {
             break;
         }
 
-        match query_executor.execute(input, user_id).await {
+        // Print generated text live, as the model produces it
+        let streamed = Arc::new(AtomicBool::new(false));
+        let sink: TokenSink = {
+            let streamed = streamed.clone();
+            Arc::new(move |delta: &str| {
+                streamed.store(true, Ordering::Relaxed);
+                print!("{}", delta);
+                io::stdout().flush().is_ok()
+            })
+        };
+        let result = query_executor.execute_streaming(input, user_id, sink).await;
+        if streamed.load(Ordering::Relaxed) {
+            println!();
+        }
+        match result {
             Ok(result) => println!("Result: {}", result),
             Err(e) => eprintln!("Error: {}", e),
         }
     }
 
+    query_executor.persist()?;
     println!("Goodbye!");
     Ok(())
 }
\ No newline at end of file
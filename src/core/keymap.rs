@@ -0,0 +1,124 @@
+use std::sync::{Arc, Mutex};
+use crate::core::RocksDBWrapper;
+use anyhow::{Result, Context};
+
+const KEY_TO_ID_PREFIX: &[u8] = b"\x00keymap:k:";
+const ID_TO_KEY_PREFIX: &[u8] = b"\x00keymap:i:";
+const NEXT_ID_KEY: &[u8] = b"\x00keymap:next";
+
+/// Bidirectional mapping between user keys and vector ids, stored in the
+/// namespace's RocksDB so search hits can be resolved back to documents.
+pub struct KeyMap {
+    db: Arc<RocksDBWrapper>,
+    allocation_lock: Mutex<()>,
+}
+
+impl KeyMap {
+    pub fn new(db: Arc<RocksDBWrapper>) -> Self {
+        Self { db, allocation_lock: Mutex::new(()) }
+    }
+
+    /// Returns the id linked to `key`, allocating and linking a fresh one if
+    /// the key has none yet.
+    pub fn assign(&self, key: &str) -> Result<u64> {
+        let _guard = self.allocation_lock.lock().unwrap();
+        if let Some(id) = self.id_for_key(key)? {
+            return Ok(id);
+        }
+        let id = match self.db.get(NEXT_ID_KEY)? {
+            Some(bytes) => decode_id(&bytes)?,
+            None => 0,
+        };
+        let mut tx = self.db.transaction();
+        tx.put(NEXT_ID_KEY, (id + 1).to_be_bytes())
+            .context("Failed to store id counter")?;
+        tx.put(key_to_id(key), id.to_be_bytes())
+            .context("Failed to store key mapping")?;
+        tx.put(id_to_key(id), key.as_bytes())
+            .context("Failed to store id mapping")?;
+        tx.commit().context("Failed to commit key mapping")?;
+        Ok(id)
+    }
+
+    /// Like `assign` for many keys at once, linking every new key in a
+    /// single transaction.
+    pub fn assign_many(&self, keys: &[String]) -> Result<Vec<u64>> {
+        let _guard = self.allocation_lock.lock().unwrap();
+        let mut next = match self.db.get(NEXT_ID_KEY)? {
+            Some(bytes) => decode_id(&bytes)?,
+            None => 0,
+        };
+        let mut ids = Vec::with_capacity(keys.len());
+        let mut tx = self.db.transaction();
+        for key in keys {
+            if let Some(id) = self.id_for_key(key)? {
+                ids.push(id);
+                continue;
+            }
+            tx.put(key_to_id(key), next.to_be_bytes())
+                .context("Failed to store key mapping")?;
+            tx.put(id_to_key(next), key.as_bytes())
+                .context("Failed to store id mapping")?;
+            ids.push(next);
+            next += 1;
+        }
+        tx.put(NEXT_ID_KEY, next.to_be_bytes())
+            .context("Failed to store id counter")?;
+        tx.commit().context("Failed to commit key mapping")?;
+        Ok(ids)
+    }
+
+    pub fn id_for_key(&self, key: &str) -> Result<Option<u64>> {
+        let value = self.db.get(&key_to_id(key))?;
+        value.map(|bytes| decode_id(&bytes)).transpose()
+    }
+
+    pub fn key_for_id(&self, id: u64) -> Result<Option<String>> {
+        let value = self.db.get(&id_to_key(id))?;
+        Ok(value.map(|bytes| String::from_utf8_lossy(&bytes).into_owned()))
+    }
+
+    /// Every id currently linked to a key.
+    pub fn ids(&self) -> Result<Vec<u64>> {
+        self.db.scan_prefix(ID_TO_KEY_PREFIX)?
+            .into_iter()
+            .map(|(key, _)| decode_id(&key[ID_TO_KEY_PREFIX.len()..]))
+            .collect()
+    }
+
+    pub fn link(&self, key: &str, id: u64) -> Result<()> {
+        let mut tx = self.db.transaction();
+        tx.put(key_to_id(key), id.to_be_bytes())
+            .context("Failed to store key mapping")?;
+        tx.put(id_to_key(id), key.as_bytes())
+            .context("Failed to store id mapping")?;
+        tx.commit().context("Failed to commit key mapping")?;
+        Ok(())
+    }
+
+    pub fn unlink(&self, key: &str) -> Result<Option<u64>> {
+        let id = match self.id_for_key(key)? {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+        let mut tx = self.db.transaction();
+        tx.delete(key_to_id(key)).context("Failed to delete key mapping")?;
+        tx.delete(id_to_key(id)).context("Failed to delete id mapping")?;
+        tx.commit().context("Failed to commit key mapping removal")?;
+        Ok(Some(id))
+    }
+}
+
+fn key_to_id(key: &str) -> Vec<u8> {
+    [KEY_TO_ID_PREFIX, key.as_bytes()].concat()
+}
+
+fn id_to_key(id: u64) -> Vec<u8> {
+    [ID_TO_KEY_PREFIX, &id.to_be_bytes()[..]].concat()
+}
+
+fn decode_id(bytes: &[u8]) -> Result<u64> {
+    let bytes: [u8; 8] = bytes.try_into()
+        .map_err(|_| anyhow::anyhow!("Corrupted key mapping entry"))?;
+    Ok(u64::from_be_bytes(bytes))
+}
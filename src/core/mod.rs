@@ -1,5 +1,11 @@
 mod rocksdb_wrapper;
 mod namespace;
+mod keymap;
+mod schema;
+#[cfg(test)]
+pub(crate) mod testing;
 
-pub use rocksdb_wrapper::RocksDBWrapper;
-pub use namespace::{Namespace, NamespaceManager};
\ No newline at end of file
+pub use rocksdb_wrapper::{RocksDBWrapper, INTERNAL_KEY_PREFIX};
+pub use namespace::{ClusterSummary, GeoHit, IndexLoadMode, InsertOutcome, Namespace, NamespaceManager, VectorField, VectorHit, VectorSearchOptions};
+pub use keymap::KeyMap;
+pub use schema::{SchemaDefinition, SchemaRegistry, SchemaViolation};
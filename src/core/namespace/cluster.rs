@@ -0,0 +1,115 @@
+use crate::vector::{KMeans, DEFAULT_VECTOR_FIELD};
+use anyhow::Result;
+use rand::seq::SliceRandom;
+use tracing::warn;
+use super::Namespace;
+
+/// Metadata object that `cluster_vectors` records each key's cluster in,
+/// under the name of the clustered vector field.
+pub const CLUSTER_METADATA_FIELD: &str = "cluster";
+
+/// A cluster found by `cluster_vectors`.
+pub struct ClusterSummary {
+    pub id: usize,
+    pub size: usize,
+    /// The member closest to the centroid.
+    pub representative: Option<String>,
+}
+
+impl Namespace {
+    /// Groups the vectors of a field into `k` clusters with k-means, trained
+    /// on a random sample of up to `sample` vectors, and records each key's
+    /// cluster in its vector metadata under `cluster.<field>`. Chunked
+    /// documents are clustered by the mean of their chunks.
+    pub fn cluster_vectors(&self, field: Option<&str>, k: usize, iterations: usize, sample: Option<usize>) -> Result<Vec<ClusterSummary>> {
+        let name = field.unwrap_or(DEFAULT_VECTOR_FIELD);
+        let field = self.vector_field(Some(name))?;
+        // Sources list the chunks of one id consecutively
+        let mut sums: Vec<(u64, Vec<f32>, usize)> = Vec::new();
+        for (id, vector) in self.vector_sources(name, &field)? {
+            match sums.last_mut() {
+                Some((last, sum, count)) if *last == id => {
+                    sum.iter_mut().zip(&vector).for_each(|(s, v)| *s += v);
+                    *count += 1;
+                }
+                _ => sums.push((id, vector, 1)),
+            }
+        }
+        // Cluster in the space the index searches
+        let vectors = sums.into_iter()
+            .map(|(id, sum, count)| {
+                let mean: Vec<f32> = sum.into_iter().map(|s| s / count as f32).collect();
+                field.config.prepare(&mean).map(|vector| (id, vector.into_owned()))
+            })
+            .collect::<Result<Vec<(u64, Vec<f32>)>>>()?;
+
+        let training: Vec<Vec<f32>> = match sample {
+            Some(sample) if sample < vectors.len() => vectors
+                .choose_multiple(&mut rand::thread_rng(), sample)
+                .map(|(_, vector)| vector.clone())
+                .collect(),
+            _ => vectors.iter().map(|(_, vector)| vector.clone()).collect(),
+        };
+        let model = KMeans::train(field.config.metric, &training, k, iterations)?;
+
+        let mut sizes = vec![0; k];
+        let mut representatives: Vec<Option<(u64, f32)>> = vec![None; k];
+        let mut assignments = Vec::with_capacity(vectors.len());
+        for (id, vector) in &vectors {
+            let (cluster, distance) = model.assign(field.config.metric, vector)?;
+            sizes[cluster] += 1;
+            if representatives[cluster].map_or(true, |(_, best)| distance < best) {
+                representatives[cluster] = Some((*id, distance));
+            }
+            assignments.push((*id, cluster));
+        }
+        {
+            let _guard = self.vector_lock.lock().unwrap();
+            for (id, cluster) in assignments {
+                if let Some(key) = self.keys.key_for_id(id)? {
+                    if let Err(e) = self.merge_vector_metadata(&key, &[CLUSTER_METADATA_FIELD, name], cluster.into()) {
+                        warn!("Not recording cluster of '{}': {:#}", key, e);
+                    }
+                }
+            }
+        }
+
+        let mut summaries = Vec::with_capacity(k);
+        for (id, (size, representative)) in sizes.into_iter().zip(representatives).enumerate() {
+            let representative = match representative {
+                Some((vector_id, _)) => self.keys.key_for_id(vector_id)?,
+                None => None,
+            };
+            summaries.push(ClusterSummary { id, size, representative });
+        }
+        Ok(summaries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::testing::temp_namespace;
+    use crate::vector::VectorConfig;
+    use usearch::{MetricKind, ScalarKind};
+
+    #[test]
+    fn test_cluster_metadata_per_field() {
+        let namespace = temp_namespace(2);
+        namespace.create_vector_field("title", VectorConfig::new(2, MetricKind::L2sq, ScalarKind::F32)).unwrap();
+        for (key, x) in [("a", 0.0), ("b", 0.1), ("c", 10.0), ("d", 10.1)] {
+            namespace.add_vector(key, None, &[x, 0.0]).unwrap();
+            namespace.add_vector(key, Some("title"), &[0.0, x]).unwrap();
+        }
+        namespace.set_vector_metadata("a", &serde_json::json!({"lang": "en"})).unwrap();
+
+        let summaries = namespace.cluster_vectors(None, 2, 10, None).unwrap();
+        assert_eq!(summaries.iter().map(|summary| summary.size).sum::<usize>(), 4);
+        namespace.cluster_vectors(Some("title"), 2, 10, None).unwrap();
+
+        // Each field keeps its own assignment next to the existing metadata
+        let metadata = namespace.vector_metadata("a").unwrap().unwrap();
+        assert_eq!(metadata["lang"], "en");
+        let cluster = &metadata["cluster"];
+        assert!(cluster["default"].is_u64() && cluster["title"].is_u64());
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::{BTreeMap, HashMap};
+use crate::vector::DEFAULT_VECTOR_FIELD;
+use anyhow::Result;
+use super::{Namespace, VectorHit, VectorSearchOptions};
+
+// Neighbours looked at per vector by `find_duplicates`. Larger groups are
+// still found through chains, without one range search per vector returning
+// the whole group.
+const DUPLICATE_NEIGHBOURS: usize = 64;
+
+impl Namespace {
+    /// Returns the closest vector within `threshold` of `vector` that belongs
+    /// to a key other than `key`, if any.
+    pub fn find_duplicate(&self, vector: &[f32], field: Option<&str>, threshold: f32, key: &str) -> Result<Option<VectorHit>> {
+        let options = VectorSearchOptions { field: field.map(str::to_string), ..Default::default() };
+        let hits = self.range_search(vector, threshold, 2, &options)?;
+        Ok(hits.into_iter().find(|hit| hit.key.as_deref() != Some(key)))
+    }
+
+    /// Groups keys whose vectors lie within `threshold` of each other,
+    /// directly or through a chain of near neighbours. Only groups of two or
+    /// more keys are returned, each sorted.
+    /// Runs one range search per stored vector while holding the vector
+    /// lock, so writes wait for the whole scan.
+    pub fn find_duplicates(&self, field: Option<&str>, threshold: f32) -> Result<Vec<Vec<String>>> {
+        let name = field.unwrap_or(DEFAULT_VECTOR_FIELD);
+        let vector_field = self.vector_field(Some(name))?;
+        let options = VectorSearchOptions { field: Some(name.to_string()), ..Default::default() };
+        let _guard = self.vector_lock.lock().unwrap();
+
+        // Union-find over vector ids
+        let mut parents: HashMap<u64, u64> = HashMap::new();
+        fn root(parents: &mut HashMap<u64, u64>, id: u64) -> u64 {
+            let mut top = id;
+            loop {
+                let parent = *parents.entry(top).or_insert(top);
+                if parent == top {
+                    break;
+                }
+                top = parent;
+            }
+            // Point the whole path at the root so later lookups are short
+            let mut current = id;
+            while current != top {
+                current = parents.insert(current, top).unwrap_or(top);
+            }
+            top
+        }
+        for (id, vector) in self.vector_sources(name, &vector_field)? {
+            for hit in self.range_search(&vector, threshold, DUPLICATE_NEIGHBOURS, &options)? {
+                let (a, b) = (root(&mut parents, id), root(&mut parents, hit.id));
+                if a != b {
+                    parents.insert(a, b);
+                }
+            }
+        }
+
+        let mut groups: BTreeMap<u64, Vec<String>> = BTreeMap::new();
+        let ids: Vec<u64> = parents.keys().copied().collect();
+        for id in ids {
+            let top = root(&mut parents, id);
+            if let Some(key) = self.keys.key_for_id(id)? {
+                groups.entry(top).or_default().push(key);
+            }
+        }
+        let mut duplicates: Vec<Vec<String>> = groups.into_values()
+            .filter(|keys| keys.len() > 1)
+            .map(|mut keys| {
+                keys.sort();
+                keys
+            })
+            .collect();
+        duplicates.sort();
+        Ok(duplicates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::InsertOutcome;
+    use crate::core::testing::temp_namespace;
+
+    #[test]
+    fn test_insert_document_dedup() {
+        let namespace = temp_namespace(2);
+        let inserted = namespace.insert_document("a", "first", None, &[0.0, 0.0], None, Some(0.5)).unwrap();
+        assert!(matches!(inserted, InsertOutcome::Inserted(_)));
+
+        match namespace.insert_document("b", "second", None, &[0.1, 0.1], None, Some(0.5)).unwrap() {
+            InsertOutcome::Duplicate(hit) => assert_eq!(hit.key.as_deref(), Some("a")),
+            InsertOutcome::Inserted(_) => panic!("near-duplicate was inserted"),
+        }
+        assert!(namespace.db.get(b"b").unwrap().is_none());
+        assert_eq!(namespace.keys.id_for_key("b").unwrap(), None);
+
+        // A key never counts as a duplicate of itself
+        let metadata = serde_json::json!({"v": 2});
+        let replaced = namespace.insert_document("a", "again", None, &[0.1, 0.1], Some(&metadata), Some(0.5)).unwrap();
+        assert!(matches!(replaced, InsertOutcome::Inserted(_)));
+        assert_eq!(namespace.vector_metadata("a").unwrap(), Some(metadata));
+        namespace.insert_document("a", "again", None, &[0.1, 0.1], None, None).unwrap();
+        assert!(namespace.vector_metadata("a").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let namespace = temp_namespace(2);
+        // a-b and b-c are close but a-c is not; they still form one group
+        namespace.add_vector("a", None, &[0.0, 0.0]).unwrap();
+        namespace.add_vector("b", None, &[0.5, 0.0]).unwrap();
+        namespace.add_vector("c", None, &[1.0, 0.0]).unwrap();
+        namespace.add_vector("d", None, &[10.0, 10.0]).unwrap();
+        namespace.add_vector("e", None, &[10.2, 10.0]).unwrap();
+        namespace.add_vector("f", None, &[-10.0, 5.0]).unwrap();
+
+        let groups = namespace.find_duplicates(None, 0.3).unwrap();
+        assert_eq!(groups, vec![vec!["a", "b", "c"], vec!["d", "e"]]);
+        assert!(namespace.find_duplicates(None, 0.01).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_find_duplicates_long_chain() {
+        let namespace = temp_namespace(2);
+        // More neighbours than one range search looks at, chained end to end
+        for i in 0..200 {
+            namespace.add_vector(&format!("k{:03}", i), None, &[i as f32 * 0.01, 0.0]).unwrap();
+        }
+        let groups = namespace.find_duplicates(None, 1.0).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].len(), 200);
+    }
+}
@@ -0,0 +1,80 @@
+use crate::vector::geo::{self, BoundingBox};
+use crate::vector::DEFAULT_VECTOR_FIELD;
+use anyhow::Result;
+use usearch::MetricKind;
+use super::{Namespace, VectorField, VectorSearchOptions};
+
+/// A point found by `geo_near`.
+pub struct GeoHit {
+    pub id: u64,
+    pub key: Option<String>,
+    pub distance_km: f32,
+}
+
+impl Namespace {
+    /// Stores the point `(lat, lon)`, in degrees, for `key` in a Haversine
+    /// field, keeping the coordinates in its metadata for bounding boxes.
+    pub fn geo_add(&self, key: &str, field: Option<&str>, lat: f64, lon: f64) -> Result<u64> {
+        self.geo_field(field)?;
+        let vector = geo::geo_vector(lat, lon)?;
+        let _guard = self.vector_lock.lock().unwrap();
+        let id = self.index_vector(key, field, &vector)?;
+        self.merge_vector_metadata(key, &["geo"], serde_json::json!({"lat": lat, "lon": lon}))?;
+        Ok(id)
+    }
+
+    /// Finds up to `k` points nearest to `(lat, lon)`, optionally no further
+    /// than `max_km` and inside `bbox`.
+    pub fn geo_near(&self, field: Option<&str>, lat: f64, lon: f64, k: usize, max_km: Option<f32>, bbox: Option<BoundingBox>) -> Result<Vec<GeoHit>> {
+        self.geo_field(field)?;
+        let query = geo::geo_vector(lat, lon)?;
+        let options = VectorSearchOptions {
+            field: field.map(str::to_string),
+            filter: bbox.map(|bbox| bbox.filter()),
+            ..Default::default()
+        };
+        let hits = match max_km {
+            Some(km) => self.range_search(&query, geo::from_km(km), k, &options)?,
+            None => self.search_vectors(&query, k, &options)?,
+        };
+        Ok(hits.into_iter()
+            .map(|hit| GeoHit { id: hit.id, key: hit.key, distance_km: geo::to_km(hit.distance) })
+            .collect())
+    }
+
+    fn geo_field(&self, field: Option<&str>) -> Result<VectorField> {
+        let vector_field = self.vector_field(field)?;
+        if vector_field.config.metric != MetricKind::Haversine {
+            return Err(anyhow::anyhow!("Vector field '{}' is not a geo field", field.unwrap_or(DEFAULT_VECTOR_FIELD)));
+        }
+        Ok(vector_field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::temp_path;
+    use crate::vector::VectorConfig;
+    use usearch::ScalarKind;
+
+    #[test]
+    fn test_geo_near() {
+        let namespace = Namespace::create(temp_path(), VectorConfig::new(2, MetricKind::Haversine, ScalarKind::F32)).unwrap();
+        namespace.geo_add("berlin", None, 52.5200, 13.4050).unwrap();
+        namespace.geo_add("paris", None, 48.8566, 2.3522).unwrap();
+        namespace.geo_add("potsdam", None, 52.3906, 13.0645).unwrap();
+
+        let hits = namespace.geo_near(None, 52.5200, 13.4050, 3, None, None).unwrap();
+        let keys: Vec<_> = hits.iter().map(|hit| hit.key.as_deref()).collect();
+        assert_eq!(keys, vec![Some("berlin"), Some("potsdam"), Some("paris")]);
+        assert!(hits[0].distance_km < 0.01);
+        assert!((hits[2].distance_km - 878.0).abs() < 5.0, "Berlin-Paris is {} km", hits[2].distance_km);
+
+        let near = namespace.geo_near(None, 52.5200, 13.4050, 10, Some(100.0), None).unwrap();
+        assert_eq!(near.len(), 2);
+        let bbox = BoundingBox::new(45.0, 0.0, 50.0, 5.0).unwrap();
+        let boxed = namespace.geo_near(None, 52.5200, 13.4050, 10, None, Some(bbox)).unwrap();
+        assert_eq!(boxed.iter().map(|hit| hit.key.as_deref()).collect::<Vec<_>>(), vec![Some("paris")]);
+    }
+}
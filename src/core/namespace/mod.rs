@@ -0,0 +1,1128 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use crate::core::{RocksDBWrapper, KeyMap, SchemaRegistry, INTERNAL_KEY_PREFIX};
+use crate::vector::{Chunk, ChunkAggregation, ChunkMatch, FilterExpr, MmrOptions, UsearchWrapper, VectorConfig, VectorLog, VectorOp, DEFAULT_VECTOR_FIELD, closest_chunks, collect_range, json_path, mmr, validate_field_name};
+use crate::search::FullTextIndex;
+use crate::graph::GraphStore;
+use anyhow::{Result, Context};
+use serde_json::Value as JsonValue;
+use tracing::{error, info, warn};
+use usearch::{MetricKind, ScalarKind};
+use persistence::latest_index_file;
+
+mod cluster;
+mod dedup;
+mod geo;
+mod persistence;
+
+pub use cluster::ClusterSummary;
+pub use geo::GeoHit;
+
+const VECTOR_FIELDS_KEY: &[u8] = b"\x00meta:vectors";
+const VECTOR_METADATA_PREFIX: &[u8] = b"\x00vmeta:";
+const VECTOR_CHUNKS_PREFIX: &[u8] = b"\x00vchunk:";
+const VECTOR_SOURCE_PREFIX: &[u8] = b"\x00vsrc:";
+// Multi-vector searches fetch this many index hits per requested key, since
+// several hits may be chunks of the same key
+const CHUNK_OVERSAMPLE: usize = 4;
+// Deleted namespaces are renamed to this prefix before their files are
+// removed, so `load` never mistakes them for live namespaces
+const DELETED_DIR_PREFIX: &str = ".deleted-";
+
+/// How saved vector indexes are brought back into memory on startup.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IndexLoadMode {
+    /// Read the whole index into memory.
+    Load,
+    /// Memory-map the index file. Falls back to `Load` when the vector log
+    /// has operations to replay, since a viewed index is immutable; a field
+    /// is likewise loaded into memory on its first write.
+    View,
+}
+
+#[derive(Default)]
+pub struct VectorSearchOptions {
+    /// Vector field to search; the default field when `None`.
+    pub field: Option<String>,
+    /// Restricts hits to vectors whose metadata or document matches.
+    pub filter: Option<FilterExpr>,
+    /// Ranking of keys in multi-vector fields.
+    pub aggregation: ChunkAggregation,
+    /// Overrides the field's `expansion_search` for this query. usearch only
+    /// has an index-wide setting, so such a query runs alone on the field,
+    /// waiting for and blocking its other searches and writes; set the
+    /// field's default instead for frequent queries.
+    pub expansion_search: Option<usize>,
+    /// Compares against every vector instead of walking the HNSW graph.
+    pub exact: bool,
+    /// Reranks a larger candidate set for diversity before taking `k`.
+    pub mmr: Option<MmrOptions>,
+}
+
+/// A vector search hit resolved back to the key it was stored under.
+pub struct VectorHit {
+    pub id: u64,
+    pub key: Option<String>,
+    /// Distance of the closest vector stored for the key.
+    pub distance: f32,
+    /// Matching chunks, closest first; empty for single-vector fields.
+    pub chunks: Vec<ChunkMatch>,
+}
+
+/// What `insert_document` did.
+pub enum InsertOutcome {
+    /// The document was stored and its vector indexed under this id.
+    Inserted(u64),
+    /// Nothing was written: another key's vector lies within the threshold.
+    Duplicate(VectorHit),
+}
+
+/// One named vector index of a namespace.
+#[derive(Clone)]
+pub struct VectorField {
+    pub config: VectorConfig,
+    pub index: Arc<UsearchWrapper>,
+}
+
+impl VectorField {
+    fn new(config: VectorConfig) -> Result<Self> {
+        config.validate()?;
+        let index = UsearchWrapper::new(&config)?;
+        Ok(Self { config, index: Arc::new(index) })
+    }
+
+    /// Applies a logged operation to the index. Logged vectors are raw, so
+    /// they go through the field's transforms first.
+    fn apply(&self, op: &VectorOp) -> Result<()> {
+        match op {
+            VectorOp::Add { id, vector, .. } if self.config.multi => self.index.add(*id, &self.config.prepare(vector)?),
+            VectorOp::Add { id, vector, .. } => self.index.replace(*id, &self.config.prepare(vector)?),
+            VectorOp::Remove { id, .. } => self.index.remove(*id).map(|_| ()),
+        }
+    }
+
+    /// Indexes raw source vectors, e.g. into a freshly created field.
+    fn fill(&self, sources: &[(u64, Vec<f32>)]) -> Result<()> {
+        self.index.reserve(sources.len())?;
+        for (id, vector) in sources {
+            self.index.add(*id, &self.config.prepare(vector)?)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct Namespace {
+    pub db: Arc<RocksDBWrapper>,
+    pub fulltext: Arc<FullTextIndex>,
+    pub keys: Arc<KeyMap>,
+    pub schema: Arc<SchemaRegistry>,
+    pub graph: Arc<GraphStore>,
+    vector_fields: Arc<RwLock<BTreeMap<String, VectorField>>>,
+    path: PathBuf,
+    vector_log: Arc<VectorLog>,
+    // Serializes vector mutations with each other and with index saves, so a
+    // saved index always matches a prefix of the vector log exactly.
+    vector_lock: Arc<Mutex<()>>,
+    // Operations made on fields while their replacement index is being
+    // built, applied to the new index before it is swapped in
+    rebuilds: Arc<Mutex<HashMap<String, Vec<VectorOp>>>>,
+    // Declared last so the namespace's files are closed before the directory
+    // is removed
+    removal: Arc<DirectoryRemoval>,
+}
+
+/// Removes a deleted namespace's directory once the last handle to the
+/// namespace is dropped.
+#[derive(Default)]
+struct DirectoryRemoval(Mutex<Option<PathBuf>>);
+
+impl Drop for DirectoryRemoval {
+    fn drop(&mut self) {
+        if let Some(path) = self.0.get_mut().unwrap().take() {
+            if let Err(e) = fs::remove_dir_all(&path) {
+                warn!("Failed to remove deleted namespace {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+impl Namespace {
+    /// Creates a namespace in `path` whose default vector field uses `config`.
+    pub fn create(path: PathBuf, config: VectorConfig) -> Result<Self> {
+        if fs::read_dir(&path).map_or(false, |mut entries| entries.next().is_some()) {
+            return Err(anyhow::anyhow!("{} already contains data", path.display()));
+        }
+        let db = RocksDBWrapper::new(&path)?;
+        let configs = BTreeMap::from([(DEFAULT_VECTOR_FIELD.to_string(), config.clone())]);
+        db.put(VECTOR_FIELDS_KEY, &serde_json::to_vec(&configs)?)?;
+        let fields = BTreeMap::from([(DEFAULT_VECTOR_FIELD.to_string(), VectorField::new(config)?)]);
+        Self::assemble(path, db, fields)
+    }
+
+    /// Reopens a namespace from `path`, restoring the latest saved index of
+    /// every vector field and replaying the vector log past it. A field whose
+    /// index file can't be read is rebuilt from the stored source vectors.
+    pub fn open(path: PathBuf, mode: IndexLoadMode) -> Result<Self> {
+        let db = RocksDBWrapper::new(&path)?;
+        let configs: BTreeMap<String, VectorConfig> = match db.get(VECTOR_FIELDS_KEY)? {
+            Some(bytes) => serde_json::from_slice(&bytes).context("Corrupted vector field catalog")?,
+            None => return Err(anyhow::anyhow!("No vector configuration found in {}", path.display())),
+        };
+        let mut fields = BTreeMap::new();
+        for (name, config) in configs {
+            fields.insert(name, VectorField::new(config)?);
+        }
+        let namespace = Self::assemble(path, db, fields)?;
+
+        let log = namespace.vector_log.entries_after(0)?;
+        for (name, field) in namespace.vector_fields() {
+            let saved = latest_index_file(&namespace.path, &name)?;
+            let checkpoint = saved.as_ref().map_or(0, |(seq, _)| *seq);
+            let pending: Vec<&VectorOp> = log.iter()
+                .filter(|(seq, op)| *seq > checkpoint && op.field() == name)
+                .map(|(_, op)| op)
+                .collect();
+            if let Some((_, file)) = &saved {
+                let restored = if mode == IndexLoadMode::View && pending.is_empty() {
+                    field.index.view(&file.to_string_lossy())
+                } else {
+                    field.index.load(&file.to_string_lossy())
+                };
+                if let Err(e) = restored {
+                    error!("Failed to read the index of vector field '{}' in {}: {:#}; rebuilding it from stored vectors", name, namespace.path.display(), e);
+                    namespace.recover_vector_field(&name, field.config, saved)?;
+                    continue;
+                }
+            }
+            for op in &pending {
+                field.apply(op)?;
+            }
+            if !pending.is_empty() {
+                info!("Replayed {} operations on vector field '{}' in {}", pending.len(), name, namespace.path.display());
+            }
+        }
+        Ok(namespace)
+    }
+
+    fn assemble(path: PathBuf, db: RocksDBWrapper, fields: BTreeMap<String, VectorField>) -> Result<Self> {
+        let db = Arc::new(db);
+        Ok(Self { 
+            fulltext: Arc::new(FullTextIndex::new(db.clone())),
+            keys: Arc::new(KeyMap::new(db.clone())),
+            schema: Arc::new(SchemaRegistry::load(db.clone())?),
+            graph: Arc::new(GraphStore::new(db.clone())),
+            vector_log: Arc::new(VectorLog::new(db.clone())),
+            vector_lock: Arc::new(Mutex::new(())),
+            rebuilds: Arc::new(Mutex::new(HashMap::new())),
+            removal: Arc::new(DirectoryRemoval::default()),
+            vector_fields: Arc::new(RwLock::new(fields)),
+            db, 
+            path,
+        })
+    }
+
+    /// Validates `value` against the namespace schema, stores it under `key`
+    /// and keeps the full-text index in sync.
+    pub fn put(&self, key: &str, value: &str) -> Result<()> {
+        if key.as_bytes().first() == Some(&INTERNAL_KEY_PREFIX) {
+            return Err(anyhow::anyhow!("Keys starting with a NUL byte are reserved"));
+        }
+        self.schema.validate(key, value)?;
+        self.db.put(key.as_bytes(), value.as_bytes())?;
+        self.fulltext.index(key, value)
+    }
+
+    /// Deletes `key` along with its full-text postings, graph edges and
+    /// linked vectors.
+    pub fn delete(&self, key: &str) -> Result<()> {
+        self.db.delete(key.as_bytes())?;
+        self.fulltext.remove(key)?;
+        self.graph.remove_node(key)?;
+        self.remove_vector(key, None)?;
+        Ok(())
+    }
+
+    /// Returns the named vector field, or the default field for `None`.
+    pub fn vector_field(&self, name: Option<&str>) -> Result<VectorField> {
+        let name = name.unwrap_or(DEFAULT_VECTOR_FIELD);
+        self.vector_fields.read().unwrap()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Vector field '{}' not found", name))
+    }
+
+    pub fn vector_fields(&self) -> Vec<(String, VectorField)> {
+        self.vector_fields.read().unwrap()
+            .iter()
+            .map(|(name, field)| (name.clone(), field.clone()))
+            .collect()
+    }
+
+    /// Adds a new named vector index with its own configuration.
+    pub fn create_vector_field(&self, name: &str, config: VectorConfig) -> Result<()> {
+        validate_field_name(name)?;
+        let _guard = self.vector_lock.lock().unwrap();
+        let mut fields = self.vector_fields.write().unwrap();
+        if fields.contains_key(name) {
+            return Err(anyhow::anyhow!("Vector field '{}' already exists", name));
+        }
+        fields.insert(name.to_string(), VectorField::new(config)?);
+        self.store_catalog(&fields)
+    }
+
+    fn store_catalog(&self, fields: &BTreeMap<String, VectorField>) -> Result<()> {
+        let configs: BTreeMap<&String, &VectorConfig> = fields.iter()
+            .map(|(name, field)| (name, &field.config))
+            .collect();
+        self.db.put(VECTOR_FIELDS_KEY, &serde_json::to_vec(&configs)?)
+    }
+
+    /// Appends `op` to the vector log, and to the pending operations of its
+    /// field if that field is being rebuilt.
+    fn log_vector_op(&self, op: VectorOp) -> Result<()> {
+        self.vector_log.append(&op)?;
+        if let Some(pending) = self.rebuilds.lock().unwrap().get_mut(op.field()) {
+            pending.push(op);
+        }
+        Ok(())
+    }
+
+    /// Indexes `vector` for `key` in `field` (the default field for `None`),
+    /// linking the key to a vector id. A vector already stored for the key in
+    /// that field is replaced.
+    pub fn add_vector(&self, key: &str, field: Option<&str>, vector: &[f32]) -> Result<u64> {
+        let _guard = self.vector_lock.lock().unwrap();
+        self.index_vector(key, field, vector)
+    }
+
+    /// Stores `value` under `key`, indexes `vector` for it in `field` and
+    /// replaces its metadata. With a `dedup_threshold`, nothing is written
+    /// when another key's vector lies that close. The check and the writes
+    /// share the vector lock, so concurrent inserts can't both pass it.
+    pub fn insert_document(&self, key: &str, value: &str, field: Option<&str>, vector: &[f32], metadata: Option<&JsonValue>, dedup_threshold: Option<f32>) -> Result<InsertOutcome> {
+        let _guard = self.vector_lock.lock().unwrap();
+        if let Some(threshold) = dedup_threshold {
+            if let Some(duplicate) = self.find_duplicate(vector, field, threshold, key)? {
+                return Ok(InsertOutcome::Duplicate(duplicate));
+            }
+        }
+        self.put(key, value)?;
+        let id = self.index_vector(key, field, vector)?;
+        // Metadata belongs to the inserted version of the document, so a
+        // re-insert without any drops what the previous version had
+        match metadata {
+            Some(metadata) => self.set_vector_metadata(key, metadata)?,
+            None => self.clear_vector_metadata(key)?,
+        }
+        Ok(InsertOutcome::Inserted(id))
+    }
+
+    /// `add_vector` for callers holding the vector lock.
+    fn index_vector(&self, key: &str, field: Option<&str>, vector: &[f32]) -> Result<u64> {
+        let name = field.unwrap_or(DEFAULT_VECTOR_FIELD);
+        let field = self.vector_field(Some(name))?;
+        if field.config.multi {
+            return Err(anyhow::anyhow!("Vector field '{}' holds chunked documents; add chunks instead", name));
+        }
+        let indexed = field.config.prepare(vector)?;
+        let id = self.keys.assign(key)?;
+        self.log_vector_op(VectorOp::Add { field: name.to_string(), id, vector: vector.to_vec() })?;
+        // Keep the source vector so the index can be rebuilt from RocksDB
+        self.db.put(&vector_source_key(name, id), &encode_vector(vector))?;
+        field.index.replace(id, &indexed)?;
+        Ok(id)
+    }
+
+    /// Bulk-loads `vectors` under the matching `keys` into `field`, replacing
+    /// vectors already stored for those keys. Every vector is checked before
+    /// anything is written; the index is filled from all cores.
+    pub fn import_vectors(&self, field: Option<&str>, keys: &[String], vectors: Vec<Vec<f32>>) -> Result<usize> {
+        if keys.len() != vectors.len() {
+            return Err(anyhow::anyhow!("Found {} keys for {} vectors", keys.len(), vectors.len()));
+        }
+        let _guard = self.vector_lock.lock().unwrap();
+        let name = field.unwrap_or(DEFAULT_VECTOR_FIELD);
+        let field = self.vector_field(Some(name))?;
+        if field.config.multi {
+            return Err(anyhow::anyhow!("Vector field '{}' holds chunked documents; add chunks instead", name));
+        }
+        let mut seen = HashSet::with_capacity(keys.len());
+        let mut indexed = Vec::with_capacity(vectors.len());
+        for (key, vector) in keys.iter().zip(&vectors) {
+            if !seen.insert(key.as_str()) {
+                return Err(anyhow::anyhow!("Key '{}' appears more than once", key));
+            }
+            indexed.push(field.config.prepare(vector).context(format!("Invalid vector for key '{}'", key))?.into_owned());
+        }
+
+        let ids = self.keys.assign_many(keys)?;
+        let mut tx = self.db.transaction();
+        for (id, vector) in ids.iter().zip(&vectors) {
+            self.log_vector_op(VectorOp::Add { field: name.to_string(), id: *id, vector: vector.clone() })?;
+            tx.put(vector_source_key(name, *id), encode_vector(vector))
+                .context("Failed to store source vector")?;
+        }
+        tx.commit().context("Failed to commit source vectors")?;
+        let items: Vec<(u64, Vec<f32>)> = ids.into_iter().zip(indexed).collect();
+        field.index.add_batch(&items)?;
+        info!("Imported {} vectors into field '{}'", items.len(), name);
+        Ok(items.len())
+    }
+
+    /// Stores `chunks` as the vectors of `key` in the multi-vector `field`,
+    /// replacing any chunks stored for the key before.
+    pub fn add_chunks(&self, key: &str, field: Option<&str>, chunks: &[Chunk]) -> Result<u64> {
+        let _guard = self.vector_lock.lock().unwrap();
+        self.index_chunks(key, None, field, chunks)
+    }
+
+    /// Stores `value` under `key` and indexes its chunks in `field`. Nothing
+    /// is written unless every chunk vector fits the field.
+    pub fn insert_chunked_document(&self, key: &str, value: &str, field: Option<&str>, chunks: &[Chunk]) -> Result<u64> {
+        let _guard = self.vector_lock.lock().unwrap();
+        self.index_chunks(key, Some(value), field, chunks)
+    }
+
+    /// `add_chunks` for callers holding the vector lock, also storing `value`
+    /// once the chunks are known to be valid.
+    fn index_chunks(&self, key: &str, value: Option<&str>, field: Option<&str>, chunks: &[Chunk]) -> Result<u64> {
+        let name = field.unwrap_or(DEFAULT_VECTOR_FIELD);
+        let field = self.vector_field(Some(name))?;
+        if !field.config.multi {
+            return Err(anyhow::anyhow!("Vector field '{}' was not created in multi-vector mode", name));
+        }
+        if chunks.is_empty() {
+            return Err(anyhow::anyhow!("At least one chunk is required"));
+        }
+        let indexed = chunks.iter()
+            .map(|chunk| field.config.prepare(&chunk.vector))
+            .collect::<Result<Vec<_>>>()?;
+        if let Some(value) = value {
+            self.put(key, value)?;
+        }
+        let id = self.keys.assign(key)?;
+        if field.index.contains(id) {
+            self.log_vector_op(VectorOp::Remove { field: name.to_string(), id })?;
+            field.index.remove(id)?;
+        }
+        for (chunk, vector) in chunks.iter().zip(&indexed) {
+            self.log_vector_op(VectorOp::Add { field: name.to_string(), id, vector: chunk.vector.clone() })?;
+            field.index.add(id, vector)?;
+        }
+        self.db.put(&vector_chunks_key(name, key), &serde_json::to_vec(chunks)?)?;
+        Ok(id)
+    }
+
+    pub fn chunks(&self, key: &str, field: Option<&str>) -> Result<Vec<Chunk>> {
+        let name = field.unwrap_or(DEFAULT_VECTOR_FIELD);
+        match self.db.get(&vector_chunks_key(name, key))? {
+            Some(bytes) => Ok(serde_json::from_slice(&bytes).context("Corrupted vector chunks")?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Removes the vector stored for `key` in `field`, or in every field for
+    /// `None`, in which case the key is also unlinked from its vector id.
+    /// Returns whether anything was removed.
+    pub fn remove_vector(&self, key: &str, field: Option<&str>) -> Result<bool> {
+        let _guard = self.vector_lock.lock().unwrap();
+        let id = match self.keys.id_for_key(key)? {
+            Some(id) => id,
+            None => return Ok(false),
+        };
+        let targets = match field {
+            Some(name) => vec![(name.to_string(), self.vector_field(Some(name))?)],
+            None => self.vector_fields(),
+        };
+        let mut removed = false;
+        for (name, field) in targets {
+            if field.index.contains(id) {
+                self.log_vector_op(VectorOp::Remove { field: name.clone(), id })?;
+                removed |= field.index.remove(id)? > 0;
+            }
+            if field.config.multi {
+                self.db.delete(&vector_chunks_key(&name, key))?;
+            } else {
+                self.db.delete(&vector_source_key(&name, id))?;
+            }
+        }
+        if field.is_none() {
+            self.keys.unlink(key)?;
+            self.clear_vector_metadata(key)?;
+        }
+        Ok(removed)
+    }
+
+    /// Attaches filterable metadata to the vectors stored for `key`.
+    pub fn set_vector_metadata(&self, key: &str, metadata: &JsonValue) -> Result<()> {
+        self.db.put(&vector_metadata_key(key), &serde_json::to_vec(metadata)?)
+    }
+
+    /// Drops the metadata attached to the vectors of `key`.
+    pub fn clear_vector_metadata(&self, key: &str) -> Result<()> {
+        self.db.delete(&vector_metadata_key(key))
+    }
+
+    /// Sets the entry at `path`, e.g. `["cluster", "default"]`, in the
+    /// metadata object of `key`, keeping the others. Callers hold the vector
+    /// lock so the write can't interleave with `insert_document`.
+    fn merge_vector_metadata(&self, key: &str, path: &[&str], value: JsonValue) -> Result<()> {
+        let (name, parents) = path.split_last().ok_or_else(|| anyhow::anyhow!("Empty metadata path"))?;
+        let mut metadata = self.vector_metadata(key)?.unwrap_or_else(|| JsonValue::Object(Default::default()));
+        let mut current = &mut metadata;
+        for part in parents {
+            let object = current.as_object_mut()
+                .ok_or_else(|| anyhow::anyhow!("Vector metadata of '{}' is not an object", key))?;
+            current = object.entry(part.to_string()).or_insert_with(|| JsonValue::Object(Default::default()));
+            if !current.is_object() {
+                *current = JsonValue::Object(Default::default());
+            }
+        }
+        current.as_object_mut()
+            .ok_or_else(|| anyhow::anyhow!("Vector metadata of '{}' is not an object", key))?
+            .insert(name.to_string(), value);
+        self.set_vector_metadata(key, &metadata)
+    }
+
+    pub fn vector_metadata(&self, key: &str) -> Result<Option<JsonValue>> {
+        match self.db.get(&vector_metadata_key(key))? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes).context("Corrupted vector metadata")?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn search_vectors(&self, vector: &[f32], k: usize, options: &VectorSearchOptions) -> Result<Vec<VectorHit>> {
+        let field = self.vector_field(options.field.as_deref())?;
+        let prepared = field.config.prepare(vector)?;
+        let vector = &*prepared;
+        if field.index.size() == 0 {
+            return Ok(Vec::new());
+        }
+        let fetch = options.mmr.map_or(k, |mmr| mmr.fetch_k(k));
+        let hits = if field.config.multi {
+            self.search_chunks(&field, vector, fetch, options)?
+        } else {
+            let mut hits = Vec::new();
+            for (id, distance) in self.index_search(&field, vector, fetch, options)? {
+                hits.push(VectorHit { id, key: self.keys.key_for_id(id)?, distance, chunks: Vec::new() });
+            }
+            hits
+        };
+        match options.mmr {
+            Some(mmr) => {
+                let name = options.field.as_deref().unwrap_or(DEFAULT_VECTOR_FIELD);
+                self.rerank_mmr(name, &field, vector, hits, k, mmr.diversity)
+            }
+            None => Ok(hits),
+        }
+    }
+
+    /// Reorders `hits` by maximal marginal relevance and keeps `k`. Keys of
+    /// multi-vector fields are represented by their closest chunk.
+    fn rerank_mmr(&self, name: &str, field: &VectorField, vector: &[f32], hits: Vec<VectorHit>, k: usize, diversity: f32) -> Result<Vec<VectorHit>> {
+        let mut candidates = Vec::with_capacity(hits.len());
+        for hit in &hits {
+            let stored = match (&hit.key, hit.chunks.first()) {
+                (Some(key), Some(best)) => self.indexed_chunks(field, name, key)?
+                    .into_iter()
+                    .nth(best.chunk)
+                    .map(|chunk| chunk.vector),
+                _ => field.index.get(hit.id)?.into_iter().next(),
+            };
+            candidates.push(stored.ok_or_else(|| anyhow::anyhow!("Vector {} is missing from the index", hit.id))?);
+        }
+        let order = mmr(field.config.metric, vector, &candidates, k, diversity)?;
+        let mut hits: Vec<Option<VectorHit>> = hits.into_iter().map(Some).collect();
+        Ok(order.into_iter().filter_map(|i| hits[i].take()).collect())
+    }
+
+    /// Finds every vector within `max_distance`, up to `limit` hits, applying
+    /// the filter and exact options. In multi-vector fields each key is
+    /// reported once, at its closest chunk.
+    pub fn range_search(&self, vector: &[f32], max_distance: f32, limit: usize, options: &VectorSearchOptions) -> Result<Vec<VectorHit>> {
+        if options.mmr.is_some() {
+            return Err(anyhow::anyhow!("Range searches return hits by distance and can't be reranked for diversity"));
+        }
+        if options.aggregation != ChunkAggregation::MaxSim {
+            return Err(anyhow::anyhow!("Range searches rank keys by their closest chunk; only the 'max' aggregation applies"));
+        }
+        let field = self.vector_field(options.field.as_deref())?;
+        let prepared = field.config.prepare(vector)?;
+        let vector = &*prepared;
+        if field.index.size() == 0 || limit == 0 {
+            return Ok(Vec::new());
+        }
+        // Several chunks of one key may fall inside the bound, so a
+        // multi-vector search can't stop at `limit` index hits
+        let fetch = if field.config.multi { field.index.size() } else { limit.min(field.index.size()) };
+        let mut seen = HashSet::new();
+        let mut hits = Vec::new();
+        for (id, distance) in collect_range(max_distance, fetch, |k| self.index_search(&field, vector, k, options))? {
+            if !seen.insert(id) {
+                continue;
+            }
+            hits.push(VectorHit { id, key: self.keys.key_for_id(id)?, distance, chunks: Vec::new() });
+            if hits.len() == limit {
+                break;
+            }
+        }
+        Ok(hits)
+    }
+
+    /// Runs one search per vector. Plain approximate searches run in parallel
+    /// inside the index; filtered, exact and multi-vector searches run in turn.
+    pub fn search_vectors_batch(&self, vectors: &[Vec<f32>], k: usize, options: &VectorSearchOptions) -> Result<Vec<Vec<VectorHit>>> {
+        let field = self.vector_field(options.field.as_deref())?;
+        if field.config.multi || options.filter.is_some() || options.exact || options.mmr.is_some() {
+            return vectors.iter().map(|vector| self.search_vectors(vector, k, options)).collect();
+        }
+        let prepared = vectors.iter()
+            .map(|vector| field.config.prepare(vector).map(|v| v.into_owned()))
+            .collect::<Result<Vec<_>>>()?;
+        if field.index.size() == 0 {
+            return Ok(vectors.iter().map(|_| Vec::new()).collect());
+        }
+        let mut results = Vec::with_capacity(vectors.len());
+        for hits in field.index.search_batch(&prepared, k, options.expansion_search)? {
+            let mut resolved = Vec::with_capacity(hits.len());
+            for (id, distance) in hits {
+                resolved.push(VectorHit { id, key: self.keys.key_for_id(id)?, distance, chunks: Vec::new() });
+            }
+            results.push(resolved);
+        }
+        Ok(results)
+    }
+
+    /// Ranks the keys of a multi-vector field. Each key appears once; its
+    /// matching chunks are rescored exactly and combined by the aggregation.
+    fn search_chunks(&self, field: &VectorField, vector: &[f32], k: usize, options: &VectorSearchOptions) -> Result<Vec<VectorHit>> {
+        let name = options.field.as_deref().unwrap_or(DEFAULT_VECTOR_FIELD);
+        let mut fetch = k * CHUNK_OVERSAMPLE;
+        let (counts, order) = loop {
+            let hits = self.index_search(field, vector, fetch, options)?;
+            let mut counts: HashMap<u64, usize> = HashMap::new();
+            let mut order = Vec::new();
+            for (id, _) in &hits {
+                let count = counts.entry(*id).or_insert(0);
+                if *count == 0 {
+                    order.push(*id);
+                }
+                *count += 1;
+            }
+            // Widen the search until k distinct keys turn up or the index is exhausted
+            if order.len() >= k || hits.len() < fetch || fetch >= field.index.size() {
+                break (counts, order);
+            }
+            fetch *= 2;
+        };
+
+        let mut ranked = Vec::new();
+        for id in order {
+            let key = match self.keys.key_for_id(id)? {
+                Some(key) => key,
+                None => continue,
+            };
+            let chunks = self.indexed_chunks(field, name, &key)?;
+            let matches = closest_chunks(field.config.metric, vector, &chunks, counts[&id])?;
+            let distance = match matches.first() {
+                Some(best) => best.distance,
+                None => continue,
+            };
+            let score = options.aggregation.score(&matches);
+            ranked.push((score, VectorHit { id, key: Some(key), distance, chunks: matches }));
+        }
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        Ok(ranked.into_iter().take(k).map(|(_, hit)| hit).collect())
+    }
+
+    /// The chunks of `key` with their vectors as the index holds them.
+    fn indexed_chunks(&self, field: &VectorField, name: &str, key: &str) -> Result<Vec<Chunk>> {
+        let mut chunks = self.chunks(key, Some(name))?;
+        if !field.config.transforms.is_empty() {
+            for chunk in &mut chunks {
+                chunk.vector = field.config.prepare(&chunk.vector)?.into_owned();
+            }
+        }
+        Ok(chunks)
+    }
+
+    fn index_search(&self, field: &VectorField, vector: &[f32], k: usize, options: &VectorSearchOptions) -> Result<Vec<(u64, f32)>> {
+        if options.exact {
+            // Filtering happens after the scan, so scan everything when filtered
+            let limit = if options.filter.is_some() { field.index.size() } else { k };
+            let mut hits = field.index.exact_search(vector, limit)?;
+            if let Some(filter) = &options.filter {
+                let mut kept = Vec::new();
+                for (id, distance) in hits {
+                    if self.vector_matches(id, filter)? {
+                        kept.push((id, distance));
+                    }
+                }
+                hits = kept;
+            }
+            hits.truncate(k);
+            return Ok(hits);
+        }
+        match &options.filter {
+            Some(filter) => {
+                // The predicate may visit the same node more than once during
+                // traversal, so remember verdicts per id
+                let verdicts: RefCell<HashMap<u64, bool>> = RefCell::new(HashMap::new());
+                field.index.filtered_search(vector, k, options.expansion_search, |id| {
+                    if let Some(verdict) = verdicts.borrow().get(&id) {
+                        return *verdict;
+                    }
+                    let verdict = self.vector_matches(id, filter).unwrap_or(false);
+                    verdicts.borrow_mut().insert(id, verdict);
+                    verdict
+                })
+            }
+            None => field.index.search(vector, k, options.expansion_search),
+        }
+    }
+
+    /// Evaluates `filter` for vector `id`. Fields resolve against the vector
+    /// metadata first and the document value second; `_key` is the key.
+    fn vector_matches(&self, id: u64, filter: &FilterExpr) -> Result<bool> {
+        let key = match self.keys.key_for_id(id)? {
+            Some(key) => key,
+            None => return Ok(false),
+        };
+        let metadata = self.vector_metadata(&key)?.unwrap_or(JsonValue::Null);
+        let document = self.db.get(key.as_bytes())?
+            .and_then(|bytes| serde_json::from_slice::<JsonValue>(&bytes).ok())
+            .unwrap_or(JsonValue::Null);
+        Ok(filter.matches(&|field: &str| {
+            if field == "_key" {
+                return Some(JsonValue::String(key.clone()));
+            }
+            json_path(&metadata, field).or_else(|| json_path(&document, field))
+        }))
+    }
+
+}
+
+fn vector_metadata_key(key: &str) -> Vec<u8> {
+    [VECTOR_METADATA_PREFIX, key.as_bytes()].concat()
+}
+
+fn vector_chunks_key(field: &str, key: &str) -> Vec<u8> {
+    [VECTOR_CHUNKS_PREFIX, field.as_bytes(), b"\x00", key.as_bytes()].concat()
+}
+
+fn vector_source_key(field: &str, id: u64) -> Vec<u8> {
+    [VECTOR_SOURCE_PREFIX, field.as_bytes(), b"\x00", &id.to_be_bytes()].concat()
+}
+
+fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|value| value.to_le_bytes()).collect()
+}
+
+fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes.chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+
+pub struct NamespaceManager {
+    namespaces: Arc<RwLock<HashMap<String, Namespace>>>,
+    data_dir: PathBuf,
+}
+
+impl NamespaceManager {
+    pub fn new() -> Self {
+        Self {
+            namespaces: Arc::new(RwLock::new(HashMap::new())),
+            data_dir: PathBuf::from("data"),
+        }
+    }
+
+    /// Opens every namespace previously created under `data_dir`.
+    pub fn load<P: AsRef<Path>>(data_dir: P, mode: IndexLoadMode) -> Result<Self> {
+        let data_dir = data_dir.as_ref().to_path_buf();
+        fs::create_dir_all(&data_dir).context("Failed to create data directory")?;
+
+        let mut namespaces = HashMap::new();
+        for entry in fs::read_dir(&data_dir).context("Failed to list data directory")? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with(DELETED_DIR_PREFIX) {
+                // Left behind by a delete that didn't finish before shutdown
+                if let Err(e) = fs::remove_dir_all(entry.path()) {
+                    warn!("Failed to remove deleted namespace '{}': {}", name, e);
+                }
+                continue;
+            }
+            match Namespace::open(entry.path(), mode) {
+                Ok(namespace) => {
+                    namespaces.insert(name, namespace);
+                }
+                Err(e) => warn!("Skipping namespace '{}': {:#}", name, e),
+            }
+        }
+        info!("Loaded {} namespaces from {}", namespaces.len(), data_dir.display());
+
+        Ok(Self {
+            namespaces: Arc::new(RwLock::new(namespaces)),
+            data_dir,
+        })
+    }
+
+    pub fn create_namespace(&self, name: &str, dimensions: usize, metric: MetricKind, scalar: ScalarKind) -> Result<()> {
+        self.create_namespace_with_config(name, VectorConfig::new(dimensions, metric, scalar))
+    }
+
+    /// Creates a namespace of points on the Earth's surface for `geo_add` and
+    /// `geo_near`.
+    pub fn create_geo_namespace(&self, name: &str) -> Result<()> {
+        self.create_namespace(name, 2, MetricKind::Haversine, ScalarKind::F32)
+    }
+
+    /// Creates a namespace whose default vector field uses `config`.
+    pub fn create_namespace_with_config(&self, name: &str, config: VectorConfig) -> Result<()> {
+        let mut namespaces = self.namespaces.write().unwrap();
+        if namespaces.contains_key(name) {
+            return Err(anyhow::anyhow!("Namespace '{}' already exists", name));
+        }
+        if name.starts_with(DELETED_DIR_PREFIX) {
+            return Err(anyhow::anyhow!("Namespace names may not start with '{}'", DELETED_DIR_PREFIX));
+        }
+        
+        let namespace = Namespace::create(self.data_dir.join(name), config)
+            .context(format!("Failed to create namespace '{}'", name))?;
+        
+        namespaces.insert(name.to_string(), namespace);
+        Ok(())
+    }
+
+    pub fn get_namespace(&self, name: &str) -> Result<Namespace> {
+        let namespaces = self.namespaces.read().unwrap();
+        namespaces.get(name)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Namespace '{}' not found", name))
+    }
+
+    /// Deletes a namespace and its files. The directory is renamed out of the
+    /// way immediately and removed once in-flight operations release it.
+    pub fn delete_namespace(&self, name: &str) -> Result<()> {
+        let mut namespaces = self.namespaces.write().unwrap();
+        let namespace = namespaces.get(name)
+            .ok_or_else(|| anyhow::anyhow!("Namespace '{}' not found", name))?;
+
+        let deleted = self.data_dir.join(format!("{}{}-{}", DELETED_DIR_PREFIX, name, uuid::Uuid::new_v4()));
+        fs::rename(&namespace.path, &deleted)
+            .context(format!("Failed to delete namespace '{}'", name))?;
+        *namespace.removal.0.lock().unwrap() = Some(deleted);
+        namespaces.remove(name);
+        Ok(())
+    }
+
+    pub fn list_namespaces(&self) -> Vec<String> {
+        let namespaces = self.namespaces.read().unwrap();
+        namespaces.keys().cloned().collect()
+    }
+
+    pub fn namespace_exists(&self, name: &str) -> bool {
+        let namespaces = self.namespaces.read().unwrap();
+        namespaces.contains_key(name)
+    }
+
+    /// Saves the vector index of every namespace that changed.
+    pub fn save_all(&self) -> Result<()> {
+        save_namespaces(&self.namespaces)
+    }
+
+    /// Saves changed vector indexes every `interval` on a background thread.
+    pub fn start_autosave(&self, interval: Duration) {
+        let namespaces = self.namespaces.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(interval);
+            if let Err(e) = save_namespaces(&namespaces) {
+                error!("Failed to save vector indexes: {:#}", e);
+            }
+        });
+    }
+}
+
+fn save_namespaces(namespaces: &RwLock<HashMap<String, Namespace>>) -> Result<()> {
+    // Snapshot the map so saves don't hold the lock against namespace changes
+    let snapshot: Vec<(String, Namespace)> = namespaces.read().unwrap()
+        .iter()
+        .map(|(name, namespace)| (name.clone(), namespace.clone()))
+        .collect();
+    for (name, namespace) in snapshot {
+        namespace.save_vectors()
+            .context(format!("Failed to save vector index for namespace '{}'", name))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::{temp_namespace, temp_path};
+
+    #[test]
+    fn test_namespace_manager() {
+        let manager = NamespaceManager::load(temp_path(), IndexLoadMode::Load).unwrap();
+
+        // Create a namespace
+        assert!(manager.create_namespace("test1", 128, MetricKind::Cos, ScalarKind::F32).is_ok());
+
+        // Check if namespace exists
+        assert!(manager.namespace_exists("test1"));
+        assert!(!manager.namespace_exists("nonexistent"));
+
+        // Try to create a duplicate namespace
+        assert!(manager.create_namespace("test1", 128, MetricKind::Cos, ScalarKind::F32).is_err());
+
+        // Get a namespace
+        let namespace = manager.get_namespace("test1");
+        assert!(namespace.is_ok());
+
+        // List namespaces
+        let namespaces = manager.list_namespaces();
+        assert_eq!(namespaces, vec!["test1"]);
+
+        // Delete a namespace
+        assert!(manager.delete_namespace("test1").is_ok());
+
+        // Try to get a deleted namespace
+        assert!(manager.get_namespace("test1").is_err());
+
+        // Try to delete a non-existent namespace
+        assert!(manager.delete_namespace("nonexistent").is_err());
+    }
+
+    #[test]
+    fn test_deleted_namespace_stays_deleted() {
+        let data_dir = temp_path();
+        let manager = NamespaceManager::load(&data_dir, IndexLoadMode::Load).unwrap();
+        manager.create_namespace("docs", 2, MetricKind::L2sq, ScalarKind::F32).unwrap();
+        let namespace = manager.get_namespace("docs").unwrap();
+        namespace.put("a", r#"{"title": "old"}"#).unwrap();
+
+        // The directory is removed once the last handle goes away
+        manager.delete_namespace("docs").unwrap();
+        assert!(!data_dir.join("docs").exists());
+        assert_eq!(fs::read_dir(&data_dir).unwrap().count(), 1);
+        drop(namespace);
+        assert_eq!(fs::read_dir(&data_dir).unwrap().count(), 0);
+
+        // Re-creating the name starts empty, and deleting it again survives a restart
+        manager.create_namespace("docs", 2, MetricKind::L2sq, ScalarKind::F32).unwrap();
+        assert!(manager.get_namespace("docs").unwrap().db.get(b"a").unwrap().is_none());
+        manager.delete_namespace("docs").unwrap();
+        drop(manager);
+        let reloaded = NamespaceManager::load(&data_dir, IndexLoadMode::Load).unwrap();
+        assert!(reloaded.list_namespaces().is_empty());
+    }
+
+    #[test]
+    fn test_create_refuses_existing_data() {
+        let path = temp_path();
+        fs::create_dir_all(&path).unwrap();
+        fs::write(path.join("stale"), b"").unwrap();
+        assert!(Namespace::create(path, VectorConfig::new(2, MetricKind::L2sq, ScalarKind::F32)).is_err());
+    }
+
+    #[test]
+    fn test_put_and_delete_update_fulltext() {
+        let namespace = temp_namespace(2);
+        namespace.put("doc", "hello world").unwrap();
+        assert_eq!(namespace.fulltext.search("hello", 10).unwrap()[0].0, "doc");
+
+        namespace.put("doc", "goodbye").unwrap();
+        assert!(namespace.fulltext.search("hello", 10).unwrap().is_empty());
+        assert_eq!(namespace.fulltext.search("goodbye", 10).unwrap().len(), 1);
+
+        namespace.delete("doc").unwrap();
+        assert!(namespace.fulltext.search("goodbye", 10).unwrap().is_empty());
+        assert!(namespace.db.get(b"doc").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_add_vector_and_search() {
+        let namespace = temp_namespace(2);
+        let a = namespace.add_vector("a", None, &[0.0, 0.0]).unwrap();
+        let b = namespace.add_vector("b", None, &[10.0, 10.0]).unwrap();
+        assert_ne!(a, b);
+        assert_eq!(namespace.keys.id_for_key("a").unwrap(), Some(a));
+
+        let hits = namespace.search_vectors(&[1.0, 1.0], 2, &VectorSearchOptions::default()).unwrap();
+        let keys: Vec<_> = hits.iter().map(|hit| hit.key.as_deref()).collect();
+        assert_eq!(keys, vec![Some("a"), Some("b")]);
+        assert_eq!(hits[0].id, a);
+        assert_eq!(hits[0].distance, 2.0);
+    }
+
+    #[test]
+    fn test_replace_vector() {
+        let path = temp_path();
+        let namespace = Namespace::create(path.clone(), VectorConfig::new(2, MetricKind::L2sq, ScalarKind::F32)).unwrap();
+        let id = namespace.add_vector("a", None, &[0.0, 0.0]).unwrap();
+        assert_eq!(namespace.add_vector("a", None, &[3.0, 4.0]).unwrap(), id);
+
+        let field = namespace.vector_field(None).unwrap();
+        assert_eq!(field.index.size(), 1);
+        assert_eq!(field.index.get(id).unwrap(), vec![vec![3.0, 4.0]]);
+        assert_eq!(namespace.source_vectors(None, id).unwrap(), vec![vec![3.0, 4.0]]);
+
+        // Replaying the vector log must end in the same state
+        drop((namespace, field));
+        let namespace = Namespace::open(path, IndexLoadMode::Load).unwrap();
+        let field = namespace.vector_field(None).unwrap();
+        assert_eq!(field.index.size(), 1);
+        assert_eq!(field.index.get(id).unwrap(), vec![vec![3.0, 4.0]]);
+    }
+
+    #[test]
+    fn test_delete_removes_vector() {
+        let namespace = temp_namespace(2);
+        namespace.put("a", "{}").unwrap();
+        let id = namespace.add_vector("a", None, &[1.0, 1.0]).unwrap();
+        namespace.add_vector("b", None, &[2.0, 2.0]).unwrap();
+        namespace.set_vector_metadata("a", &serde_json::json!({"tag": "x"})).unwrap();
+
+        namespace.delete("a").unwrap();
+        let field = namespace.vector_field(None).unwrap();
+        assert!(!field.index.contains(id));
+        assert_eq!(field.index.size(), 1);
+        assert_eq!(namespace.keys.id_for_key("a").unwrap(), None);
+        assert!(namespace.vector_metadata("a").unwrap().is_none());
+        assert!(namespace.source_vectors(None, id).unwrap().is_empty());
+
+        let hits = namespace.search_vectors(&[1.0, 1.0], 10, &VectorSearchOptions::default()).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].key.as_deref(), Some("b"));
+        assert!(!namespace.remove_vector("a", None).unwrap());
+    }
+
+    #[test]
+    fn test_insert_chunked_document_validates_first() {
+        let namespace = temp_namespace(2);
+        let mut config = VectorConfig::new(2, MetricKind::L2sq, ScalarKind::F32);
+        config.multi = true;
+        namespace.create_vector_field("chunks", config).unwrap();
+
+        let bad = [Chunk { start: 0, end: 2, vector: vec![0.0, 0.0] }, Chunk { start: 2, end: 4, vector: vec![1.0] }];
+        assert!(namespace.insert_chunked_document("a", "abcd", Some("chunks"), &bad).is_err());
+        assert!(namespace.db.get(b"a").unwrap().is_none());
+        assert_eq!(namespace.keys.id_for_key("a").unwrap(), None);
+
+        let good = [Chunk { start: 0, end: 2, vector: vec![0.0, 0.0] }, Chunk { start: 2, end: 4, vector: vec![1.0, 1.0] }];
+        let id = namespace.insert_chunked_document("a", "abcd", Some("chunks"), &good).unwrap();
+        assert_eq!(namespace.db.get(b"a").unwrap().as_deref(), Some(&b"abcd"[..]));
+        assert_eq!(namespace.chunks("a", Some("chunks")).unwrap().len(), 2);
+        assert_eq!(namespace.source_vectors(Some("chunks"), id).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_filtered_search() {
+        let namespace = temp_namespace(2);
+        namespace.put("a", r#"{"lang": "de"}"#).unwrap();
+        namespace.add_vector("a", None, &[0.0, 0.0]).unwrap();
+        namespace.add_vector("b", None, &[1.0, 1.0]).unwrap();
+        namespace.set_vector_metadata("b", &serde_json::json!({"lang": "en", "year": 2021})).unwrap();
+
+        let search = |filter: &str| {
+            let options = VectorSearchOptions { filter: Some(FilterExpr::parse(filter).unwrap()), ..Default::default() };
+            namespace.search_vectors(&[0.0, 0.0], 10, &options).unwrap()
+                .into_iter()
+                .filter_map(|hit| hit.key)
+                .collect::<Vec<_>>()
+        };
+        // Fields resolve against the metadata first, then the document
+        assert_eq!(search("lang = 'de'"), vec!["a"]);
+        assert_eq!(search("year > 2020"), vec!["b"]);
+        assert_eq!(search("_key in ('a', 'b')"), vec!["a", "b"]);
+
+        namespace.clear_vector_metadata("b").unwrap();
+        assert!(search("year > 2020").is_empty());
+    }
+
+    #[test]
+    fn test_named_vector_fields() {
+        let path = temp_path();
+        let namespace = Namespace::create(path.clone(), VectorConfig::new(2, MetricKind::L2sq, ScalarKind::F32)).unwrap();
+        namespace.create_vector_field("title", VectorConfig::new(3, MetricKind::L2sq, ScalarKind::F32)).unwrap();
+        assert!(namespace.create_vector_field("title", VectorConfig::new(3, MetricKind::L2sq, ScalarKind::F32)).is_err());
+
+        namespace.add_vector("a", None, &[0.0, 0.0]).unwrap();
+        namespace.add_vector("a", Some("title"), &[1.0, 2.0, 3.0]).unwrap();
+        namespace.add_vector("b", Some("title"), &[9.0, 9.0, 9.0]).unwrap();
+        // Each field checks vectors against its own dimensions
+        assert!(namespace.add_vector("b", None, &[1.0, 2.0, 3.0]).is_err());
+        assert!(namespace.add_vector("b", Some("missing"), &[1.0, 2.0]).is_err());
+
+        let title = VectorSearchOptions { field: Some("title".to_string()), ..Default::default() };
+        let hits = namespace.search_vectors(&[1.0, 2.0, 3.0], 10, &title).unwrap();
+        let keys: Vec<_> = hits.iter().map(|hit| hit.key.as_deref()).collect();
+        assert_eq!(keys, vec![Some("a"), Some("b")]);
+        let hits = namespace.search_vectors(&[0.0, 0.0], 10, &VectorSearchOptions::default()).unwrap();
+        assert_eq!(hits.len(), 1);
+
+        // Removing from one field leaves the others alone
+        assert!(namespace.remove_vector("a", Some("title")).unwrap());
+        assert_eq!(namespace.vector_field(Some("title")).unwrap().index.size(), 1);
+        assert_eq!(namespace.vector_field(None).unwrap().index.size(), 1);
+
+        // Fields and their contents survive a restart
+        drop(namespace);
+        let namespace = Namespace::open(path, IndexLoadMode::Load).unwrap();
+        let names: Vec<_> = namespace.vector_fields().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec![DEFAULT_VECTOR_FIELD.to_string(), "title".to_string()]);
+        let field = namespace.vector_field(Some("title")).unwrap();
+        assert_eq!(field.config.dimensions, 3);
+        assert_eq!(field.index.size(), 1);
+    }
+
+    #[test]
+    fn test_search_expansion_override() {
+        let namespace = temp_namespace(2);
+        namespace.add_vector("a", None, &[0.0, 0.0]).unwrap();
+        let field = namespace.vector_field(None).unwrap();
+        let default = field.index.expansion_search();
+        let options = VectorSearchOptions { expansion_search: Some(default + 100), ..Default::default() };
+        assert_eq!(namespace.search_vectors(&[0.0, 0.0], 1, &options).unwrap().len(), 1);
+        // The override only lasts for the query
+        assert_eq!(field.index.expansion_search(), default);
+    }
+
+    #[test]
+    fn test_range_search_options() {
+        let namespace = temp_namespace(2);
+        for i in 0..100 {
+            let key = format!("k{}", i);
+            namespace.add_vector(&key, None, &[i as f32, 0.0]).unwrap();
+            namespace.set_vector_metadata(&key, &serde_json::json!({"even": i % 2 == 0})).unwrap();
+        }
+        let keys = |hits: Vec<VectorHit>| hits.into_iter().filter_map(|hit| hit.key).collect::<Vec<_>>();
+
+        // Squared distances, so this reaches k0..=k40 over several rounds
+        let all = namespace.range_search(&[0.0, 0.0], 1600.0, usize::MAX, &VectorSearchOptions::default()).unwrap();
+        assert_eq!(all.len(), 41);
+
+        let even = VectorSearchOptions { filter: Some(FilterExpr::parse("even = true").unwrap()), ..Default::default() };
+        let expected: Vec<_> = (0..=40).step_by(2).map(|i| format!("k{}", i)).collect();
+        assert_eq!(keys(namespace.range_search(&[0.0, 0.0], 1600.0, usize::MAX, &even).unwrap()), expected);
+        assert_eq!(keys(namespace.range_search(&[0.0, 0.0], 1600.0, 5, &even).unwrap()), expected[..5]);
+
+        let exact = VectorSearchOptions { exact: true, filter: even.filter.clone(), ..Default::default() };
+        assert_eq!(keys(namespace.range_search(&[0.0, 0.0], 1600.0, usize::MAX, &exact).unwrap()), expected);
+
+        let diverse = VectorSearchOptions { mmr: Some(MmrOptions::new(0.5, None).unwrap()), ..Default::default() };
+        assert!(namespace.range_search(&[0.0, 0.0], 1600.0, 10, &diverse).is_err());
+        let summed = VectorSearchOptions { aggregation: ChunkAggregation::Sum, ..Default::default() };
+        assert!(namespace.range_search(&[0.0, 0.0], 1600.0, 10, &summed).is_err());
+    }
+}
@@ -0,0 +1,282 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::vector::{Pca, VectorConfig, VectorTransform, DEFAULT_VECTOR_FIELD, apply_transforms, transformed_dimensions};
+use anyhow::{Result, Context};
+use rand::seq::SliceRandom;
+use tracing::{error, info};
+use super::{Namespace, VectorField, decode_vector, vector_source_key};
+
+const INDEX_FILE_PREFIX: &str = "vectors-";
+const INDEX_FILE_EXTENSION: &str = "usearch";
+
+/// Saving, restoring and rebuilding the vector indexes of a namespace.
+impl Namespace {
+    /// Replaces the index of field `name` with one built from the source
+    /// vectors, which already reflect every logged operation, and saves it in
+    /// place of the unreadable `previous` file.
+    pub(super) fn recover_vector_field(&self, name: &str, config: VectorConfig, previous: Option<(u64, PathBuf)>) -> Result<()> {
+        let field = VectorField::new(config)?;
+        let sources = self.vector_sources(name, &field)?;
+        field.fill(&sources)?;
+        self.save_field(name, &field, self.vector_log.last_seq()?, previous)?;
+        self.vector_fields.write().unwrap().insert(name.to_string(), field);
+        info!("Rebuilt vector field '{}' in {} from {} vectors", name, self.path.display(), sources.len());
+        Ok(())
+    }
+
+    /// Writes every vector field's index to disk if anything changed since
+    /// the last save, then drops the vector log entries the new files cover.
+    pub fn save_vectors(&self) -> Result<()> {
+        let _guard = self.vector_lock.lock().unwrap();
+        let seq = self.vector_log.last_seq()?;
+        let fields = self.vector_fields();
+
+        let mut saved = Vec::new();
+        for (name, _) in &fields {
+            saved.push(latest_index_file(&self.path, name)?);
+        }
+        let up_to_date = saved.iter().all(|file| file.as_ref().map_or(0, |(saved_seq, _)| *saved_seq) == seq);
+        if up_to_date {
+            return Ok(());
+        }
+
+        for ((name, field), previous) in fields.iter().zip(saved) {
+            self.save_field(name, field, seq, previous)?;
+        }
+        self.vector_log.truncate(seq)
+    }
+
+    /// Saves one field's index as of log position `seq`, replacing `previous`.
+    fn save_field(&self, name: &str, field: &VectorField, seq: u64, previous: Option<(u64, PathBuf)>) -> Result<()> {
+        // Save under a temporary name and rename, so a crash never leaves
+        // a half-written file that looks like a valid index
+        let file = self.path.join(format!("{}{}-{:020}.{}", INDEX_FILE_PREFIX, name, seq, INDEX_FILE_EXTENSION));
+        let tmp = file.with_extension("tmp");
+        field.index.save(&tmp.to_string_lossy())?;
+        fs::rename(&tmp, &file).context("Failed to move saved index into place")?;
+
+        if let Some((_, old)) = previous.filter(|(_, old)| *old != file) {
+            fs::remove_file(old).context("Failed to remove previous index file")?;
+        }
+        Ok(())
+    }
+
+    /// Rebuilds a vector field's index from the vectors stored in RocksDB,
+    /// e.g. after corruption or to apply new HNSW parameters or transforms
+    /// in `config`.
+    /// The build runs on a background thread; searches keep using the old
+    /// index until the new one is swapped in.
+    pub fn rebuild_vector_field(&self, name: &str, config: VectorConfig) -> Result<()> {
+        let current = self.vector_field(Some(name))?;
+        if config.metric != current.config.metric || config.multi != current.config.multi {
+            return Err(anyhow::anyhow!("A rebuild can only change the quantization, HNSW parameters and transforms"));
+        }
+        if config.dimensions != current.config.dimensions && config.transforms == current.config.transforms {
+            return Err(anyhow::anyhow!("Dimensions can only change along with the vector transforms"));
+        }
+        let fresh = VectorField::new(config)?;
+        let sources = {
+            let _guard = self.vector_lock.lock().unwrap();
+            let mut rebuilds = self.rebuilds.lock().unwrap();
+            if rebuilds.contains_key(name) {
+                return Err(anyhow::anyhow!("Vector field '{}' is already being rebuilt", name));
+            }
+            let sources = self.vector_sources(name, &current)?;
+            rebuilds.insert(name.to_string(), Vec::new());
+            sources
+        };
+
+        let namespace = self.clone();
+        let name = name.to_string();
+        std::thread::spawn(move || {
+            if let Err(e) = namespace.finish_rebuild(&name, fresh, sources) {
+                namespace.rebuilds.lock().unwrap().remove(&name);
+                error!("Failed to rebuild vector field '{}' in {}: {:#}", name, namespace.path.display(), e);
+            }
+        });
+        Ok(())
+    }
+
+    /// Replaces the transforms of a vector field and rebuilds its index in
+    /// the background to match. Returns the dimensions the field indexes.
+    pub fn set_vector_transforms(&self, field: Option<&str>, transforms: Vec<VectorTransform>) -> Result<usize> {
+        let name = field.unwrap_or(DEFAULT_VECTOR_FIELD);
+        let current = self.vector_field(Some(name))?;
+        let dimensions = match transformed_dimensions(&transforms)? {
+            Some(dimensions) => dimensions,
+            None => self.raw_dimensions(name, &current)?,
+        };
+        let mut config = current.config.clone();
+        config.dimensions = dimensions;
+        config.transforms = transforms;
+        config.validate()?;
+        self.rebuild_vector_field(name, config)?;
+        Ok(dimensions)
+    }
+
+    /// Fits a PCA down to `dimensions` on a random sample of up to `sample`
+    /// stored vectors of a field, run through `preceding` transforms first.
+    pub fn fit_pca(&self, field: Option<&str>, preceding: &[VectorTransform], dimensions: usize, sample: usize) -> Result<Pca> {
+        let name = field.unwrap_or(DEFAULT_VECTOR_FIELD);
+        let vector_field = self.vector_field(Some(name))?;
+        let mut ids = self.keys.ids()?;
+        ids.shuffle(&mut rand::thread_rng());
+        let mut vectors = Vec::new();
+        for id in ids {
+            if vectors.len() >= sample {
+                break;
+            }
+            for vector in self.field_sources(name, &vector_field, id)? {
+                vectors.push(apply_transforms(preceding, &vector)?);
+            }
+        }
+        info!("Fitting PCA to {} dimensions on {} vectors of field '{}'", dimensions, vectors.len(), name);
+        Pca::fit(&vectors, dimensions)
+    }
+
+    /// Length of the vectors inserted into a field, before its transforms.
+    fn raw_dimensions(&self, name: &str, field: &VectorField) -> Result<usize> {
+        if transformed_dimensions(&field.config.transforms)?.is_none() {
+            return Ok(field.config.dimensions);
+        }
+        for id in self.keys.ids()? {
+            if let Some(vector) = self.field_sources(name, field, id)?.into_iter().next() {
+                return Ok(vector.len());
+            }
+        }
+        Err(anyhow::anyhow!("Vector field '{}' has no vectors to tell its input dimensions", name))
+    }
+
+    fn finish_rebuild(&self, name: &str, fresh: VectorField, sources: Vec<(u64, Vec<f32>)>) -> Result<()> {
+        fresh.fill(&sources)?;
+
+        let _guard = self.vector_lock.lock().unwrap();
+        let pending = self.rebuilds.lock().unwrap().remove(name).unwrap_or_default();
+        for op in &pending {
+            fresh.apply(op)?;
+        }
+        {
+            let mut fields = self.vector_fields.write().unwrap();
+            fields.insert(name.to_string(), fresh.clone());
+            self.store_catalog(&fields)?;
+        }
+        // Save right away so a restart doesn't load the old index file
+        let seq = self.vector_log.last_seq()?;
+        self.save_field(name, &fresh, seq, latest_index_file(&self.path, name)?)?;
+        info!("Rebuilt vector field '{}' in {} from {} vectors", name, self.path.display(), sources.len());
+        Ok(())
+    }
+
+    /// Collects the stored vectors of a field as `(id, vector)` pairs.
+    pub(super) fn vector_sources(&self, name: &str, field: &VectorField) -> Result<Vec<(u64, Vec<f32>)>> {
+        let mut sources = Vec::new();
+        for id in self.keys.ids()? {
+            sources.extend(self.field_sources(name, field, id)?.into_iter().map(|vector| (id, vector)));
+        }
+        Ok(sources)
+    }
+
+    /// The vectors stored for `id` in `field` as they were inserted, before
+    /// any transforms.
+    pub fn source_vectors(&self, field: Option<&str>, id: u64) -> Result<Vec<Vec<f32>>> {
+        let name = field.unwrap_or(DEFAULT_VECTOR_FIELD);
+        self.field_sources(name, &self.vector_field(Some(name))?, id)
+    }
+
+    pub(super) fn field_sources(&self, name: &str, field: &VectorField, id: u64) -> Result<Vec<Vec<f32>>> {
+        if field.config.multi {
+            return match self.keys.key_for_id(id)? {
+                Some(key) => Ok(self.chunks(&key, Some(name))?.into_iter().map(|chunk| chunk.vector).collect()),
+                None => Ok(Vec::new()),
+            };
+        }
+        match self.db.get(&vector_source_key(name, id))? {
+            Some(bytes) => Ok(vec![decode_vector(&bytes)]),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+/// Finds the saved index of `field` with the highest log sequence number in
+/// `dir`. Files are named `vectors-<field>-<seq>.usearch`.
+pub(super) fn latest_index_file(dir: &Path, field: &str) -> Result<Option<(u64, PathBuf)>> {
+    let mut latest: Option<(u64, PathBuf)> = None;
+    for entry in fs::read_dir(dir).context("Failed to list namespace directory")? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(INDEX_FILE_EXTENSION) {
+            continue;
+        }
+        let stem = match path.file_stem().and_then(|stem| stem.to_str()).and_then(|stem| stem.strip_prefix(INDEX_FILE_PREFIX)) {
+            Some(stem) => stem,
+            None => continue,
+        };
+        let (file_field, seq) = match stem.rsplit_once('-') {
+            Some(parts) => parts,
+            None => continue,
+        };
+        if file_field != field {
+            continue;
+        }
+        if let Ok(seq) = seq.parse::<u64>() {
+            if latest.as_ref().map_or(true, |(best, _)| seq > *best) {
+                latest = Some((seq, path));
+            }
+        }
+    }
+    Ok(latest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{IndexLoadMode, VectorSearchOptions};
+    use crate::core::testing::temp_path;
+    use usearch::{MetricKind, ScalarKind};
+
+    #[test]
+    fn test_write_to_viewed_index() {
+        let path = temp_path();
+        let namespace = Namespace::create(path.clone(), VectorConfig::new(2, MetricKind::L2sq, ScalarKind::F32)).unwrap();
+        namespace.add_vector("a", None, &[0.0, 0.0]).unwrap();
+        namespace.add_vector("b", None, &[5.0, 5.0]).unwrap();
+        namespace.save_vectors().unwrap();
+        drop(namespace);
+
+        let namespace = Namespace::open(path.clone(), IndexLoadMode::View).unwrap();
+        namespace.add_vector("c", None, &[1.0, 1.0]).unwrap();
+        assert!(namespace.remove_vector("b", None).unwrap());
+        let hits = namespace.search_vectors(&[0.0, 0.0], 3, &VectorSearchOptions::default()).unwrap();
+        let keys: Vec<_> = hits.iter().map(|hit| hit.key.as_deref()).collect();
+        assert_eq!(keys, vec![Some("a"), Some("c")]);
+
+        // The loaded copy can replace the file it was viewed from
+        namespace.save_vectors().unwrap();
+        drop(namespace);
+        let namespace = Namespace::open(path, IndexLoadMode::View).unwrap();
+        assert_eq!(namespace.vector_field(None).unwrap().index.size(), 2);
+    }
+
+    #[test]
+    fn test_open_rebuilds_corrupt_index() {
+        let path = temp_path();
+        let namespace = Namespace::create(path.clone(), VectorConfig::new(2, MetricKind::L2sq, ScalarKind::F32)).unwrap();
+        namespace.add_vector("a", None, &[0.0, 0.0]).unwrap();
+        namespace.add_vector("b", None, &[5.0, 5.0]).unwrap();
+        namespace.save_vectors().unwrap();
+        namespace.add_vector("c", None, &[1.0, 1.0]).unwrap();
+        drop(namespace);
+
+        let (_, file) = latest_index_file(&path, DEFAULT_VECTOR_FIELD).unwrap().unwrap();
+        fs::write(&file, b"not an index").unwrap();
+
+        let namespace = Namespace::open(path.clone(), IndexLoadMode::Load).unwrap();
+        let hits = namespace.search_vectors(&[0.0, 0.0], 3, &VectorSearchOptions::default()).unwrap();
+        let keys: Vec<_> = hits.iter().map(|hit| hit.key.as_deref()).collect();
+        assert_eq!(keys, vec![Some("a"), Some("c"), Some("b")]);
+
+        // The rebuilt index was saved over the corrupt file
+        drop(namespace);
+        let namespace = Namespace::open(path, IndexLoadMode::View).unwrap();
+        assert_eq!(namespace.vector_field(None).unwrap().index.size(), 3);
+    }
+}
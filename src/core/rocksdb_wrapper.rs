@@ -1,7 +1,11 @@
-use rocksdb::{TransactionDB, Options, WriteOptions, Transaction};
+use rocksdb::{TransactionDB, Options, WriteOptions, Transaction, IteratorMode};
 use std::path::Path;
 use anyhow::{Result, Context};
 
+/// Keys starting with this byte are reserved for internal bookkeeping
+/// (indexes, mappings, metadata) and are hidden from `scan`.
+pub const INTERNAL_KEY_PREFIX: u8 = 0x00;
+
 pub struct RocksDBWrapper {
     db: TransactionDB,
 }
@@ -36,6 +40,31 @@ impl RocksDBWrapper {
         Ok(())
     }
 
+    /// Returns every user key/value pair, skipping internal entries.
+    pub fn scan(&self) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut entries = Vec::new();
+        for item in self.db.iterator(IteratorMode::Start) {
+            let (key, value) = item.context("Failed to iterate over DB")?;
+            if key.first() == Some(&INTERNAL_KEY_PREFIX) {
+                continue;
+            }
+            entries.push((key.to_vec(), value.to_vec()));
+        }
+        Ok(entries)
+    }
+
+    pub fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut entries = Vec::new();
+        for item in self.db.prefix_iterator(prefix) {
+            let (key, value) = item.context("Failed to iterate over DB")?;
+            if !key.starts_with(prefix) {
+                break;
+            }
+            entries.push((key.to_vec(), value.to_vec()));
+        }
+        Ok(entries)
+    }
+
     pub fn transaction(&self) -> Transaction<TransactionDB> {
         self.db.transaction()
     }
@@ -0,0 +1,236 @@
+use std::sync::{Arc, RwLock};
+use crate::core::RocksDBWrapper;
+use anyhow::{Result, Context, anyhow};
+use jsonschema::JSONSchema;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+const VERSION_PREFIX: &[u8] = b"\x00schema:v:";
+const CURRENT_KEY: &[u8] = b"\x00schema:current";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SchemaDefinition {
+    pub version: u32,
+    pub schema: JsonValue,
+    pub key_pattern: Option<String>,
+}
+
+/// A key that fails validation, together with every reason it failed.
+pub struct SchemaViolation {
+    pub key: String,
+    pub errors: Vec<String>,
+}
+
+struct CompiledSchema {
+    definition: SchemaDefinition,
+    validator: JSONSchema,
+    key_pattern: Option<Regex>,
+}
+
+impl CompiledSchema {
+    fn compile(definition: SchemaDefinition) -> Result<Self> {
+        let validator = JSONSchema::compile(&definition.schema)
+            .map_err(|e| anyhow!("Invalid JSON Schema: {}", e))?;
+        let key_pattern = definition.key_pattern.as_deref()
+            .map(|pattern| Regex::new(&format!("^(?:{})$", pattern)))
+            .transpose()
+            .context("Invalid key pattern")?;
+        Ok(Self { definition, validator, key_pattern })
+    }
+
+    fn check(&self, key: &str, value: &str) -> Vec<String> {
+        let mut errors = Vec::new();
+        if let Some(pattern) = &self.key_pattern {
+            if !pattern.is_match(key) {
+                errors.push(format!("key '{}' does not match pattern '{}'", key, pattern.as_str()));
+            }
+        }
+        match serde_json::from_str::<JsonValue>(value) {
+            Ok(instance) => {
+                if let Err(validation_errors) = self.validator.validate(&instance) {
+                    for error in validation_errors {
+                        errors.push(format!("{}: {}", error.instance_path, error));
+                    }
+                }
+            }
+            Err(e) => errors.push(format!("value is not valid JSON: {}", e)),
+        }
+        errors
+    }
+}
+
+/// Versioned JSON Schema attached to a namespace. Every write is validated
+/// against the current version; older versions are kept for reference.
+pub struct SchemaRegistry {
+    db: Arc<RocksDBWrapper>,
+    current: RwLock<Option<CompiledSchema>>,
+}
+
+impl SchemaRegistry {
+    pub fn load(db: Arc<RocksDBWrapper>) -> Result<Self> {
+        let current = match db.get(CURRENT_KEY)? {
+            Some(bytes) => {
+                let version = decode_version(&bytes)?;
+                let definition = read_definition(&db, version)?
+                    .ok_or_else(|| anyhow!("Schema version {} is missing", version))?;
+                Some(CompiledSchema::compile(definition)?)
+            }
+            None => None,
+        };
+        Ok(Self { db, current: RwLock::new(current) })
+    }
+
+    /// Stores `schema` as a new version and makes it current.
+    pub fn set(&self, schema: JsonValue, key_pattern: Option<String>) -> Result<u32> {
+        let mut current = self.current.write().unwrap();
+        let version = self.versions()?.last().map_or(1, |latest| latest + 1);
+        let compiled = CompiledSchema::compile(SchemaDefinition { version, schema, key_pattern })?;
+
+        let mut tx = self.db.transaction();
+        tx.put(version_key(version), serde_json::to_vec(&compiled.definition)?)
+            .context("Failed to store schema")?;
+        tx.put(CURRENT_KEY, version.to_be_bytes())
+            .context("Failed to store current schema version")?;
+        tx.commit().context("Failed to commit schema")?;
+
+        *current = Some(compiled);
+        Ok(version)
+    }
+
+    /// Stops enforcing a schema. Previous versions stay in the history.
+    pub fn clear(&self) -> Result<()> {
+        let mut current = self.current.write().unwrap();
+        self.db.delete(CURRENT_KEY)?;
+        *current = None;
+        Ok(())
+    }
+
+    pub fn current(&self) -> Option<SchemaDefinition> {
+        self.current.read().unwrap().as_ref().map(|c| c.definition.clone())
+    }
+
+    pub fn get(&self, version: u32) -> Result<Option<SchemaDefinition>> {
+        read_definition(&self.db, version)
+    }
+
+    pub fn versions(&self) -> Result<Vec<u32>> {
+        self.db.scan_prefix(VERSION_PREFIX)?
+            .iter()
+            .map(|(key, _)| decode_version(&key[VERSION_PREFIX.len()..]))
+            .collect()
+    }
+
+    pub fn validate(&self, key: &str, value: &str) -> Result<()> {
+        let current = self.current.read().unwrap();
+        if let Some(compiled) = current.as_ref() {
+            let errors = compiled.check(key, value);
+            if !errors.is_empty() {
+                return Err(anyhow!(
+                    "Schema violation (version {}): {}",
+                    compiled.definition.version,
+                    errors.join("; ")
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks every stored key against a proposed schema without applying it.
+    pub fn dry_run(&self, schema: JsonValue, key_pattern: Option<String>) -> Result<Vec<SchemaViolation>> {
+        let compiled = CompiledSchema::compile(SchemaDefinition { version: 0, schema, key_pattern })?;
+        let mut violations = Vec::new();
+        for (key, value) in self.db.scan()? {
+            let key = String::from_utf8_lossy(&key).into_owned();
+            let errors = compiled.check(&key, &String::from_utf8_lossy(&value));
+            if !errors.is_empty() {
+                violations.push(SchemaViolation { key, errors });
+            }
+        }
+        Ok(violations)
+    }
+}
+
+fn read_definition(db: &RocksDBWrapper, version: u32) -> Result<Option<SchemaDefinition>> {
+    match db.get(&version_key(version))? {
+        Some(bytes) => Ok(Some(serde_json::from_slice(&bytes).context("Corrupted schema definition")?)),
+        None => Ok(None),
+    }
+}
+
+fn version_key(version: u32) -> Vec<u8> {
+    [VERSION_PREFIX, &version.to_be_bytes()[..]].concat()
+}
+
+fn decode_version(bytes: &[u8]) -> Result<u32> {
+    let bytes: [u8; 4] = bytes.try_into()
+        .map_err(|_| anyhow!("Corrupted schema version"))?;
+    Ok(u32::from_be_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::temp_db;
+    use serde_json::json;
+
+    #[test]
+    fn test_versions() {
+        let db = temp_db();
+        let registry = SchemaRegistry::load(db.clone()).unwrap();
+        assert!(registry.current().is_none());
+        assert_eq!(registry.set(json!({"type": "object"}), None).unwrap(), 1);
+        assert_eq!(registry.set(json!({"type": "array"}), Some("item[0-9]+".to_string())).unwrap(), 2);
+        assert!(registry.set(json!({"type": 5}), None).is_err());
+        assert_eq!(registry.versions().unwrap(), vec![1, 2]);
+        assert_eq!(registry.get(1).unwrap().unwrap().schema, json!({"type": "object"}));
+        assert!(registry.get(3).unwrap().is_none());
+
+        // The current version survives a reload
+        let reloaded = SchemaRegistry::load(db).unwrap();
+        let current = reloaded.current().unwrap();
+        assert_eq!(current.version, 2);
+        assert_eq!(current.key_pattern.as_deref(), Some("item[0-9]+"));
+    }
+
+    #[test]
+    fn test_validate_and_clear() {
+        let db = temp_db();
+        let registry = SchemaRegistry::load(db.clone()).unwrap();
+        assert!(registry.validate("anything", "not json").is_ok());
+
+        let schema = json!({"type": "object", "required": ["name"]});
+        registry.set(schema, Some("user[0-9]+".to_string())).unwrap();
+        assert!(registry.validate("user1", r#"{"name": "Alice"}"#).is_ok());
+        let error = registry.validate("user1", r#"{"age": 3}"#).unwrap_err().to_string();
+        assert!(error.contains("version 1"), "{}", error);
+        // The pattern must match the whole key
+        assert!(registry.validate("user1x", r#"{"name": "Alice"}"#).is_err());
+        assert!(registry.validate("user2", "not json").is_err());
+
+        registry.clear().unwrap();
+        assert!(registry.validate("user1", r#"{"age": 3}"#).is_ok());
+        assert_eq!(registry.versions().unwrap(), vec![1]);
+        assert!(SchemaRegistry::load(db).unwrap().current().is_none());
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let db = temp_db();
+        db.put(b"user1", br#"{"name": "Alice"}"#).unwrap();
+        db.put(b"user2", br#"{"age": 3}"#).unwrap();
+        db.put(b"admin", br#"{"name": "Root"}"#).unwrap();
+        let registry = SchemaRegistry::load(db).unwrap();
+
+        let schema = json!({"type": "object", "required": ["name"]});
+        let mut violations = registry.dry_run(schema, Some("user[0-9]+".to_string())).unwrap();
+        violations.sort_by(|a, b| a.key.cmp(&b.key));
+        let keys: Vec<&str> = violations.iter().map(|v| v.key.as_str()).collect();
+        assert_eq!(keys, vec!["admin", "user2"]);
+        assert!(registry.current().is_none());
+
+        // The registry's own entries are not checked
+        registry.set(json!({"type": "object"}), None).unwrap();
+        assert!(registry.dry_run(json!({"type": "object"}), None).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,20 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use crate::core::{Namespace, RocksDBWrapper};
+use crate::vector::VectorConfig;
+use usearch::{MetricKind, ScalarKind};
+
+/// A fresh path under the system temp directory, not yet created.
+pub fn temp_path() -> PathBuf {
+    std::env::temp_dir().join(format!("whitematter-test-{}", uuid::Uuid::new_v4()))
+}
+
+pub fn temp_db() -> Arc<RocksDBWrapper> {
+    Arc::new(RocksDBWrapper::new(temp_path()).unwrap())
+}
+
+/// A namespace whose default field holds `dimensions`-dimensional f32
+/// vectors compared by squared Euclidean distance.
+pub fn temp_namespace(dimensions: usize) -> Namespace {
+    Namespace::create(temp_path(), VectorConfig::new(dimensions, MetricKind::L2sq, ScalarKind::F32)).unwrap()
+}
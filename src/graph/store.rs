@@ -0,0 +1,299 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use crate::core::RocksDBWrapper;
+use anyhow::{Result, Context, anyhow};
+use serde_json::Value as JsonValue;
+
+const OUTGOING_PREFIX: &[u8] = b"\x00graph:o:";
+const INCOMING_PREFIX: &[u8] = b"\x00graph:i:";
+const SEPARATOR: u8 = 0x00;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Outgoing,
+    Incoming,
+    Both,
+}
+
+impl Direction {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "out" => Ok(Direction::Outgoing),
+            "in" => Ok(Direction::Incoming),
+            "both" => Ok(Direction::Both),
+            _ => Err(anyhow!("Invalid direction '{}'", name)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub label: String,
+    pub props: JsonValue,
+}
+
+/// Labelled, directed edges between keys of a namespace. Every edge is
+/// written twice, once under its source and once under its target, so
+/// neighbours can be listed in either direction with a prefix scan.
+pub struct GraphStore {
+    db: Arc<RocksDBWrapper>,
+}
+
+impl GraphStore {
+    pub fn new(db: Arc<RocksDBWrapper>) -> Self {
+        Self { db }
+    }
+
+    pub fn link(&self, from: &str, to: &str, label: &str, props: JsonValue) -> Result<()> {
+        check_component(from)?;
+        check_component(to)?;
+        check_component(label)?;
+        let props = serde_json::to_vec(&props)?;
+        let mut tx = self.db.transaction();
+        tx.put(edge_key(OUTGOING_PREFIX, from, label, to), &props)
+            .context("Failed to store outgoing edge")?;
+        tx.put(edge_key(INCOMING_PREFIX, to, label, from), &props)
+            .context("Failed to store incoming edge")?;
+        tx.commit().context("Failed to commit edge")?;
+        Ok(())
+    }
+
+    /// Removes the edges from `from` to `to`, restricted to `label` if given.
+    /// Returns the number of edges removed.
+    pub fn unlink(&self, from: &str, to: &str, label: Option<&str>) -> Result<usize> {
+        let edges: Vec<Edge> = self.neighbors(from, label, Direction::Outgoing)?
+            .into_iter()
+            .filter(|edge| edge.to == to)
+            .collect();
+        self.delete_edges(&edges)?;
+        Ok(edges.len())
+    }
+
+    /// Removes every edge touching `key` in either direction.
+    pub fn remove_node(&self, key: &str) -> Result<()> {
+        let edges = self.neighbors(key, None, Direction::Both)?;
+        self.delete_edges(&edges)
+    }
+
+    pub fn neighbors(&self, key: &str, label: Option<&str>, direction: Direction) -> Result<Vec<Edge>> {
+        let mut edges = Vec::new();
+        if matches!(direction, Direction::Outgoing | Direction::Both) {
+            for (label, other, props) in self.scan_edges(OUTGOING_PREFIX, key, label)? {
+                edges.push(Edge { from: key.to_string(), to: other, label, props });
+            }
+        }
+        if matches!(direction, Direction::Incoming | Direction::Both) {
+            for (label, other, props) in self.scan_edges(INCOMING_PREFIX, key, label)? {
+                edges.push(Edge { from: other, to: key.to_string(), label, props });
+            }
+        }
+        Ok(edges)
+    }
+
+    /// Breadth-first traversal from `start`, returning each reachable key with
+    /// its hop distance. `start` itself is not included.
+    pub fn traverse(
+        &self,
+        start: &str,
+        max_depth: usize,
+        label: Option<&str>,
+        direction: Direction,
+        limit: usize,
+    ) -> Result<Vec<(String, usize)>> {
+        let mut visited = HashSet::from([start.to_string()]);
+        let mut queue = VecDeque::from([(start.to_string(), 0)]);
+        let mut reached = Vec::new();
+
+        while let Some((key, depth)) = queue.pop_front() {
+            if depth == max_depth {
+                continue;
+            }
+            for neighbor in self.adjacent(&key, label, direction)? {
+                if visited.insert(neighbor.clone()) {
+                    if reached.len() >= limit {
+                        return Ok(reached);
+                    }
+                    reached.push((neighbor.clone(), depth + 1));
+                    queue.push_back((neighbor, depth + 1));
+                }
+            }
+        }
+        Ok(reached)
+    }
+
+    /// Returns the keys along a shortest path from `from` to `to`, both ends
+    /// included, or `None` if `to` is not reachable within `max_depth` hops.
+    pub fn shortest_path(
+        &self,
+        from: &str,
+        to: &str,
+        max_depth: usize,
+        label: Option<&str>,
+        direction: Direction,
+    ) -> Result<Option<Vec<String>>> {
+        if from == to {
+            return Ok(Some(vec![from.to_string()]));
+        }
+        let mut parents: HashMap<String, String> = HashMap::new();
+        let mut visited = HashSet::from([from.to_string()]);
+        let mut queue = VecDeque::from([(from.to_string(), 0)]);
+
+        while let Some((key, depth)) = queue.pop_front() {
+            if depth == max_depth {
+                continue;
+            }
+            for neighbor in self.adjacent(&key, label, direction)? {
+                if !visited.insert(neighbor.clone()) {
+                    continue;
+                }
+                parents.insert(neighbor.clone(), key.clone());
+                if neighbor == to {
+                    let mut path = vec![neighbor];
+                    while let Some(parent) = parents.get(path.last().unwrap()) {
+                        path.push(parent.clone());
+                    }
+                    path.reverse();
+                    return Ok(Some(path));
+                }
+                queue.push_back((neighbor, depth + 1));
+            }
+        }
+        Ok(None)
+    }
+
+    fn adjacent(&self, key: &str, label: Option<&str>, direction: Direction) -> Result<Vec<String>> {
+        Ok(self.neighbors(key, label, direction)?
+            .into_iter()
+            .map(|edge| if edge.from == key { edge.to } else { edge.from })
+            .collect())
+    }
+
+    fn scan_edges(&self, prefix: &[u8], key: &str, label: Option<&str>) -> Result<Vec<(String, String, JsonValue)>> {
+        let mut scan_prefix = [prefix, key.as_bytes(), &[SEPARATOR]].concat();
+        if let Some(label) = label {
+            scan_prefix.extend_from_slice(label.as_bytes());
+            scan_prefix.push(SEPARATOR);
+        }
+        let node_prefix_len = prefix.len() + key.len() + 1;
+
+        let mut edges = Vec::new();
+        for (edge_key, props) in self.db.scan_prefix(&scan_prefix)? {
+            let rest = &edge_key[node_prefix_len..];
+            let split = rest.iter().position(|b| *b == SEPARATOR)
+                .ok_or_else(|| anyhow!("Corrupted graph edge"))?;
+            let label = String::from_utf8_lossy(&rest[..split]).into_owned();
+            let other = String::from_utf8_lossy(&rest[split + 1..]).into_owned();
+            let props = serde_json::from_slice(&props).context("Corrupted edge properties")?;
+            edges.push((label, other, props));
+        }
+        Ok(edges)
+    }
+
+    fn delete_edges(&self, edges: &[Edge]) -> Result<()> {
+        let mut tx = self.db.transaction();
+        for edge in edges {
+            tx.delete(edge_key(OUTGOING_PREFIX, &edge.from, &edge.label, &edge.to))
+                .context("Failed to delete outgoing edge")?;
+            tx.delete(edge_key(INCOMING_PREFIX, &edge.to, &edge.label, &edge.from))
+                .context("Failed to delete incoming edge")?;
+        }
+        tx.commit().context("Failed to commit edge removal")?;
+        Ok(())
+    }
+}
+
+fn edge_key(prefix: &[u8], node: &str, label: &str, other: &str) -> Vec<u8> {
+    [prefix, node.as_bytes(), &[SEPARATOR], label.as_bytes(), &[SEPARATOR], other.as_bytes()].concat()
+}
+
+fn check_component(value: &str) -> Result<()> {
+    if value.as_bytes().contains(&SEPARATOR) {
+        return Err(anyhow!("Graph keys and labels may not contain NUL bytes"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::temp_db;
+    use serde_json::json;
+
+    /// a -cites-> b -cites-> c -cites-> d, plus a -likes-> c
+    fn sample_graph() -> GraphStore {
+        let graph = GraphStore::new(temp_db());
+        graph.link("a", "b", "cites", json!({"page": 4})).unwrap();
+        graph.link("b", "c", "cites", JsonValue::Null).unwrap();
+        graph.link("c", "d", "cites", JsonValue::Null).unwrap();
+        graph.link("a", "c", "likes", JsonValue::Null).unwrap();
+        graph
+    }
+
+    fn sorted(mut reached: Vec<(String, usize)>) -> Vec<(String, usize)> {
+        reached.sort();
+        reached
+    }
+
+    #[test]
+    fn test_link_and_neighbors() {
+        let graph = sample_graph();
+        let out = graph.neighbors("a", None, Direction::Outgoing).unwrap();
+        assert_eq!(out.len(), 2);
+        let cites = graph.neighbors("a", Some("cites"), Direction::Outgoing).unwrap();
+        assert_eq!(cites.len(), 1);
+        assert_eq!(cites[0].to, "b");
+        assert_eq!(cites[0].props, json!({"page": 4}));
+
+        let incoming = graph.neighbors("c", None, Direction::Incoming).unwrap();
+        let mut sources: Vec<&str> = incoming.iter().map(|edge| edge.from.as_str()).collect();
+        sources.sort();
+        assert_eq!(sources, vec!["a", "b"]);
+        assert_eq!(graph.neighbors("c", None, Direction::Both).unwrap().len(), 3);
+        assert!(graph.link("a\0", "b", "cites", JsonValue::Null).is_err());
+    }
+
+    #[test]
+    fn test_unlink_and_remove_node() {
+        let graph = sample_graph();
+        assert_eq!(graph.unlink("a", "c", Some("cites")).unwrap(), 0);
+        assert_eq!(graph.unlink("a", "c", None).unwrap(), 1);
+        assert!(graph.neighbors("c", None, Direction::Incoming).unwrap().iter().all(|edge| edge.from != "a"));
+
+        graph.remove_node("c").unwrap();
+        assert!(graph.neighbors("c", None, Direction::Both).unwrap().is_empty());
+        assert!(graph.neighbors("b", None, Direction::Outgoing).unwrap().is_empty());
+        assert!(graph.neighbors("d", None, Direction::Incoming).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_traverse() {
+        let graph = sample_graph();
+        let reached = graph.traverse("a", 10, None, Direction::Outgoing, usize::MAX).unwrap();
+        assert_eq!(sorted(reached), vec![("b".to_string(), 1), ("c".to_string(), 1), ("d".to_string(), 2)]);
+
+        let cites = graph.traverse("a", 2, Some("cites"), Direction::Outgoing, usize::MAX).unwrap();
+        assert_eq!(cites, vec![("b".to_string(), 1), ("c".to_string(), 2)]);
+        let back = graph.traverse("d", 10, None, Direction::Incoming, usize::MAX).unwrap();
+        assert_eq!(back.len(), 3);
+
+        assert!(graph.traverse("a", 10, None, Direction::Outgoing, 0).unwrap().is_empty());
+        assert_eq!(graph.traverse("a", 10, None, Direction::Outgoing, 2).unwrap().len(), 2);
+        assert!(graph.traverse("a", 0, None, Direction::Outgoing, usize::MAX).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_shortest_path() {
+        let graph = sample_graph();
+        let path = graph.shortest_path("a", "d", 10, None, Direction::Outgoing).unwrap();
+        assert_eq!(path, Some(vec!["a".to_string(), "c".to_string(), "d".to_string()]));
+        let cites = graph.shortest_path("a", "d", 10, Some("cites"), Direction::Outgoing).unwrap();
+        assert_eq!(cites.unwrap().len(), 4);
+
+        assert_eq!(graph.shortest_path("a", "d", 1, None, Direction::Outgoing).unwrap(), None);
+        assert_eq!(graph.shortest_path("d", "a", 10, None, Direction::Outgoing).unwrap(), None);
+        assert!(graph.shortest_path("d", "a", 10, None, Direction::Both).unwrap().is_some());
+        assert_eq!(graph.shortest_path("a", "a", 0, None, Direction::Outgoing).unwrap(), Some(vec!["a".to_string()]));
+    }
+}
@@ -0,0 +1,143 @@
+use rlua::{Context, Error, Result, Table, Value};
+use serde_json::{Map, Number, Value as JsonValue};
+
+/// Deepest table nesting `lua_to_json` converts. Self-referencing tables
+/// would otherwise recurse until the stack overflows.
+const MAX_DEPTH: usize = 64;
+
+/// Converts a Lua value into JSON. Tables whose keys are exactly `1..n` become
+/// arrays, every other table becomes an object keyed by the stringified keys.
+pub fn lua_to_json(value: Value) -> Result<JsonValue> {
+    value_to_json(value, 0)
+}
+
+fn value_to_json(value: Value, depth: usize) -> Result<JsonValue> {
+    match value {
+        Value::Nil => Ok(JsonValue::Null),
+        Value::Boolean(b) => Ok(JsonValue::Bool(b)),
+        Value::Integer(i) => Ok(JsonValue::from(i)),
+        Value::Number(n) => Number::from_f64(n)
+            .map(JsonValue::Number)
+            .ok_or_else(|| Error::RuntimeError(format!("Cannot represent {} in JSON", n))),
+        Value::String(s) => Ok(JsonValue::String(s.to_str()?.to_owned())),
+        Value::Table(table) => table_to_json(table, depth + 1),
+        _ => Err(Error::RuntimeError("Unsupported Lua type for JSON conversion".to_string())),
+    }
+}
+
+fn table_to_json(table: Table, depth: usize) -> Result<JsonValue> {
+    if depth > MAX_DEPTH {
+        return Err(Error::RuntimeError(format!("Table nesting exceeds {} levels or contains a cycle", MAX_DEPTH)));
+    }
+    let len = table.raw_len();
+    let mut entries = Vec::new();
+    for pair in table.pairs::<Value, Value>() {
+        entries.push(pair?);
+    }
+
+    let is_array = len > 0
+        && entries.len() as i64 == len
+        && entries.iter().all(|(key, _)| matches!(key, Value::Integer(i) if (1..=len).contains(i)));
+    if is_array {
+        let mut array = vec![JsonValue::Null; len as usize];
+        for (key, value) in entries {
+            if let Value::Integer(i) = key {
+                array[i as usize - 1] = value_to_json(value, depth)?;
+            }
+        }
+        return Ok(JsonValue::Array(array));
+    }
+
+    let mut object = Map::new();
+    for (key, value) in entries {
+        let key = match key {
+            Value::String(s) => s.to_str()?.to_owned(),
+            Value::Integer(i) => i.to_string(),
+            Value::Number(n) => n.to_string(),
+            _ => return Err(Error::RuntimeError("Unsupported table key for JSON conversion".to_string())),
+        };
+        object.insert(key, value_to_json(value, depth)?);
+    }
+    Ok(JsonValue::Object(object))
+}
+
+pub fn json_to_lua<'lua>(ctx: Context<'lua>, value: &JsonValue) -> Result<Value<'lua>> {
+    match value {
+        JsonValue::Null => Ok(Value::Nil),
+        JsonValue::Bool(b) => Ok(Value::Boolean(*b)),
+        JsonValue::Number(n) => match n.as_i64() {
+            Some(i) => Ok(Value::Integer(i)),
+            None => Ok(Value::Number(n.as_f64().unwrap_or(f64::NAN))),
+        },
+        JsonValue::String(s) => Ok(Value::String(ctx.create_string(s)?)),
+        JsonValue::Array(array) => {
+            let table = ctx.create_table()?;
+            for (i, item) in array.iter().enumerate() {
+                table.set(i + 1, json_to_lua(ctx, item)?)?;
+            }
+            Ok(Value::Table(table))
+        }
+        JsonValue::Object(object) => {
+            let table = ctx.create_table()?;
+            for (key, item) in object {
+                table.set(key.as_str(), json_to_lua(ctx, item)?)?;
+            }
+            Ok(Value::Table(table))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rlua::Lua;
+    use serde_json::json;
+
+    #[test]
+    fn test_lua_to_json() {
+        Lua::new().context(|ctx| {
+            let value: Value = ctx.load(r#"{name = "Alice", tags = {"a", "b"}, age = 30, score = 1.5, admin = false, [7] = "seven"}"#).eval().unwrap();
+            let json = lua_to_json(value).unwrap();
+            assert_eq!(json, json!({"name": "Alice", "tags": ["a", "b"], "age": 30, "score": 1.5, "admin": false, "7": "seven"}));
+
+            // Sparse or empty tables aren't arrays
+            let sparse: Value = ctx.load("{[1] = 'a', [3] = 'c'}").eval().unwrap();
+            assert_eq!(lua_to_json(sparse).unwrap(), json!({"1": "a", "3": "c"}));
+            let empty: Value = ctx.load("{}").eval().unwrap();
+            assert_eq!(lua_to_json(empty).unwrap(), json!({}));
+
+            // Cycles and runaway nesting are errors, not stack overflows
+            let cyclic: Value = ctx.load("local t = {name = 'loop'}; t.self = t; return t").eval().unwrap();
+            assert!(lua_to_json(cyclic).is_err());
+            let deep: Value = ctx.load("local t = {} for i = 1, 100 do t = {t} end return t").eval().unwrap();
+            assert!(lua_to_json(deep).is_err());
+            let nested: Value = ctx.load("local t = {} for i = 1, 10 do t = {t} end return t").eval().unwrap();
+            assert!(lua_to_json(nested).is_ok());
+
+            let long: Value = ctx.load("local t = {} for i = 1, 10000 do t[i] = i end return t").eval().unwrap();
+            let array = lua_to_json(long).unwrap();
+            assert_eq!(array.as_array().unwrap().len(), 10000);
+            assert_eq!(array[9999], json!(10000));
+
+            assert!(lua_to_json(Value::Number(f64::NAN)).is_err());
+            let function: Value = ctx.load("function() end").eval().unwrap();
+            assert!(lua_to_json(function).is_err());
+        });
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        Lua::new().context(|ctx| {
+            let json = json!({"name": "Alice", "tags": ["a", {"b": [1, 2.5]}], "admin": true, "age": 30});
+            let value = json_to_lua(ctx, &json).unwrap();
+            assert_eq!(lua_to_json(value).unwrap(), json);
+
+            let table = match json_to_lua(ctx, &json!([10, 20])).unwrap() {
+                Value::Table(table) => table,
+                _ => panic!("expected a table"),
+            };
+            assert_eq!(table.get::<_, i64>(2).unwrap(), 20);
+            assert!(matches!(json_to_lua(ctx, &JsonValue::Null).unwrap(), Value::Nil));
+        });
+    }
+}
@@ -1,5 +1,6 @@
 mod vm;
 mod luarocks;
+pub mod convert;
 
 pub use vm::LuaVM;
 pub use luarocks::LuaRocks;
\ No newline at end of file
@@ -7,9 +7,11 @@ mod query;
 mod cli;
 mod server;
 mod auth;
+mod search;
+mod graph;
 
 use clap::{Parser, Subcommand};
-use crate::core::{RocksDBWrapper, NamespaceManager};
+use crate::core::{RocksDBWrapper, NamespaceManager, IndexLoadMode};
 use crate::vector::UsearchWrapper;
 use crate::ai::{LLMWrapper, EmbeddingWrapper};
 use crate::lua::LuaVM;
@@ -35,12 +37,65 @@ struct Cli {
 
     #[arg(long)]
     tokenizer_path: String,
+
+    #[arg(long, default_value = "data")]
+    data_dir: String,
+
+    /// Seconds between background saves of changed vector indexes
+    #[arg(long, default_value_t = 60)]
+    autosave_interval: u64,
+
+    /// Memory-map saved vector indexes instead of loading them into RAM
+    #[arg(long)]
+    mmap_indexes: bool,
+
+    /// Directory the import_vectors query function may read files from;
+    /// the function is disabled when not set
+    #[arg(long)]
+    import_dir: Option<std::path::PathBuf>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     Cli,
     Server { port: Option<u16> },
+    /// Bulk-load vectors from a .npy, .fvecs or .bvecs file
+    Import {
+        namespace: String,
+        vectors: std::path::PathBuf,
+        /// Keys for the vector rows: one per line, or a 1-D .npy array of ids.
+        /// Rows are keyed by their number when omitted
+        #[arg(long)]
+        keys: Option<std::path::PathBuf>,
+        #[arg(long)]
+        field: Option<String>,
+    },
+    /// Measure vector search quality and speed
+    Bench {
+        #[command(subcommand)]
+        command: BenchCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum BenchCommand {
+    /// Compare approximate search with exact search: recall@k and latency
+    Recall {
+        namespace: String,
+        #[arg(long)]
+        field: Option<String>,
+        #[arg(long, default_value_t = 10)]
+        k: usize,
+        /// Stored vectors to sample as queries when --queries is not given
+        #[arg(long, default_value_t = 1000)]
+        samples: usize,
+        /// Query set as .fvecs or .npy
+        #[arg(long)]
+        queries: Option<std::path::PathBuf>,
+        /// Comma-separated expansion_search values to compare
+        #[arg(long, value_delimiter = ',')]
+        expansion: Vec<usize>,
+    },
 }
 
 #[tokio::main]
@@ -55,7 +110,32 @@ async fn main() -> Result<()> {
         _ => anyhow::bail!("Invalid device specified"),
     };
 
-    let namespace_manager = NamespaceManager::new();
+    let load_mode = if cli.mmap_indexes { IndexLoadMode::View } else { IndexLoadMode::Load };
+    let namespace_manager = NamespaceManager::load(&cli.data_dir, load_mode)?;
+    if let Some(Commands::Bench { command }) = &cli.command {
+        // Benchmarks only need the vector indexes, not the models
+        match command {
+            BenchCommand::Recall { namespace, field, k, samples, queries, expansion } => {
+                cli::bench::recall(&namespace_manager, &cli::bench::RecallOptions {
+                    namespace: namespace.clone(),
+                    field: field.clone(),
+                    k: *k,
+                    samples: *samples,
+                    queries: queries.clone(),
+                    expansions: expansion.clone(),
+                })?;
+            }
+        }
+        return Ok(());
+    }
+    if let Some(Commands::Import { namespace, vectors, keys, field }) = &cli.command {
+        let (keys, vectors) = vector::io::read_dataset(vectors, keys.as_deref())?;
+        let imported = namespace_manager.get_namespace(namespace)?.import_vectors(field.as_deref(), &keys, vectors)?;
+        namespace_manager.save_all()?;
+        println!("Imported {} vectors into '{}'", imported, namespace);
+        return Ok(());
+    }
+    namespace_manager.start_autosave(std::time::Duration::from_secs(cli.autosave_interval));
     let llm = LLMWrapper::new(cli.model_path.into(), cli.tokenizer_path.into(), device)?;
     let embedding = EmbeddingWrapper::new()?;
     let lua_vm = LuaVM::new(std::path::PathBuf::from("path/to/luarocks"))?;
@@ -66,14 +146,44 @@ async fn main() -> Result<()> {
     auth_manager.add_user("admin", vec![
         "select".to_string(),
         "insert".to_string(),
+        "insert_with_embedding".to_string(),
+        "insert_chunks".to_string(),
         "update".to_string(),
         "delete".to_string(),
         "create_namespace".to_string(),
         "delete_namespace".to_string(),
+        "create_vector_field".to_string(),
+        "list_vector_fields".to_string(),
+        "namespace_stats".to_string(),
+        "set_schema".to_string(),
+        "get_schema".to_string(),
+        "validate_schema".to_string(),
+        "drop_schema".to_string(),
         "upload_file".to_string(),
         "process_file".to_string(),
         "generate_embedding".to_string(),
         "similarity_search".to_string(),
+        "similarity_search_batch".to_string(),
+        "range_search".to_string(),
+        "update_vector".to_string(),
+        "delete_vector".to_string(),
+        "reserve".to_string(),
+        "import_vectors".to_string(),
+        "rebuild_vector_index".to_string(),
+        "set_vector_transforms".to_string(),
+        "cluster".to_string(),
+        "find_duplicates".to_string(),
+        "create_geo_namespace".to_string(),
+        "geo_add".to_string(),
+        "geo_near".to_string(),
+        "hybrid_search".to_string(),
+        "link".to_string(),
+        "unlink".to_string(),
+        "neighbors".to_string(),
+        "traverse".to_string(),
+        "shortest_path".to_string(),
+        "join".to_string(),
+        "aggregate".to_string(),
         "llm_query".to_string(),
     ]);
 
@@ -86,6 +196,7 @@ async fn main() -> Result<()> {
         auth_manager,
         5,  // max_concurrent_llm
         10, // max_concurrent_embedding
+        cli.import_dir.clone(),
     );
 
     match &cli.command {
@@ -96,8 +207,11 @@ async fn main() -> Result<()> {
             let port = port.unwrap_or(3000);
             run_server(port, query_executor).await?;
         }
+        Some(Commands::Bench { .. }) | Some(Commands::Import { .. }) => {
+            unreachable!("benchmarks and imports run before the models load")
+        }
         None => {
-            println!("Please specify a command: cli or server");
+            println!("Please specify a command: cli, server, bench or import");
         }
     }
 
@@ -1,17 +1,31 @@
-use crate::core::NamespaceManager;
-use crate::ai::{LLMWrapper, EmbeddingWrapper};
+use crate::core::{InsertOutcome, Namespace, NamespaceManager, VectorField, VectorHit, VectorSearchOptions};
+use crate::ai::{LLMWrapper, EmbeddingWrapper, GenerationConfig};
 use crate::lua::LuaVM;
 use crate::file::FileStorage;
 use crate::auth::AuthManager;
+use crate::search::{hybrid_search, FusionMethod, HybridOptions};
+use crate::graph::Direction;
+use crate::lua::convert::{lua_to_json, json_to_lua};
+use crate::query::parser::{QueryParser, QueryType};
+use crate::query::operators::{self, AggregateFunction, AggregateSpec, JoinSpec, KEY_FIELD};
 use anyhow::{Result, Context};
 use tokio::sync::Semaphore;
 use std::sync::{Arc, RwLock};
 use std::cell::RefCell;
+use std::path::{Path, PathBuf};
 use tracing::{info, error, instrument};
-use rlua::{Context as LuaContext, Error as LuaError, Lua, Value as LuaValue};
+use rlua::{Context as LuaContext, Error as LuaError, Function as LuaFunction, Lua, MultiValue as LuaMultiValue, Table as LuaTable, Value as LuaValue};
 use candle_core::Device;
+use crate::vector::geo::BoundingBox;
+use crate::vector::io::{read_dataset, resolve_within};
+use crate::vector::{metric_name, parse_metric, parse_scalar, scalar_name, unpack_bits, Chunk, ChunkAggregation, FilterExpr, MmrOptions, VectorConfig, VectorTransform, DEFAULT_VECTOR_FIELD};
 use usearch::{MetricKind, ScalarKind};
 
+/// Receives the text deltas of `llm_query` calls while a query runs.
+/// Returning `false` stops the generation, e.g. once a streaming client has
+/// gone away.
+pub type TokenSink = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
 pub struct QueryExecutor {
     namespace_manager: Arc<RwLock<NamespaceManager>>,
     llm: Arc<RwLock<LLMWrapper>>,
@@ -21,6 +35,8 @@ pub struct QueryExecutor {
     auth_manager: Arc<RwLock<AuthManager>>,
     llm_semaphore: Arc<Semaphore>,
     embedding_semaphore: Arc<Semaphore>,
+    // Directory `import_vectors` may read from; imports are refused without one
+    import_dir: Option<PathBuf>,
 }
 
 impl QueryExecutor {
@@ -33,6 +49,7 @@ impl QueryExecutor {
         auth_manager: AuthManager,
         max_concurrent_llm: usize,
         max_concurrent_embedding: usize,
+        import_dir: Option<PathBuf>,
     ) -> Self {
         Self {
             namespace_manager: Arc::new(RwLock::new(namespace_manager)),
@@ -43,13 +60,38 @@ impl QueryExecutor {
             auth_manager: Arc::new(RwLock::new(auth_manager)),
             llm_semaphore: Arc::new(Semaphore::new(max_concurrent_llm)),
             embedding_semaphore: Arc::new(Semaphore::new(max_concurrent_embedding)),
+            import_dir,
         }
     }
 
     #[instrument(skip(self, query))]
     pub async fn execute(&self, query: &str, user_id: &str) -> Result<String> {
+        self.run(query, user_id, None).await
+    }
+
+    /// Like `execute`, passing the text generated by `llm_query` to `sink`
+    /// as it is produced.
+    #[instrument(skip(self, query, sink))]
+    pub async fn execute_streaming(&self, query: &str, user_id: &str, sink: TokenSink) -> Result<String> {
+        self.run(query, user_id, Some(sink)).await
+    }
+
+    async fn run(&self, query: &str, user_id: &str, sink: Option<TokenSink>) -> Result<String> {
+        // Lua, storage and model inference all block; keep them off the async
+        // workers so other requests and streamed events keep moving
+        tokio::task::block_in_place(|| self.run_blocking(query, user_id, sink))
+    }
+
+    fn run_blocking(&self, query: &str, user_id: &str, sink: Option<TokenSink>) -> Result<String> {
+        // Relational operators also have a textual command form, e.g.
+        // `join posts users author` or `aggregate orders sum amount by region`;
+        // anything else, including Lua calls to `join`/`aggregate`, runs as Lua
+        if let Some((query_type, args)) = QueryParser::parse_command(query) {
+            return self.execute_command(query_type, &args, user_id);
+        }
+
         let result = self.lua_vm.read().unwrap().execute_with_context(|lua_ctx| {
-            self.register_db_functions(&lua_ctx, user_id.to_string())
+            self.register_db_functions(&lua_ctx, user_id.to_string(), sink)
                 .map_err(|e| LuaError::ExternalError(Arc::new(e)))?;
             lua_ctx.load(query).eval()
         })?;
@@ -65,7 +107,39 @@ impl QueryExecutor {
         }
     }
 
-    fn register_db_functions(&self, lua_ctx: &LuaContext, user_id: String) -> Result<()> {
+    /// Flushes every changed vector index to disk. Call on graceful shutdown.
+    pub fn persist(&self) -> Result<()> {
+        self.namespace_manager.read().unwrap().save_all()
+    }
+
+    fn execute_command(&self, query_type: QueryType, args: &[String], user_id: &str) -> Result<String> {
+        let auth_manager = self.auth_manager.read().unwrap();
+        let namespace_manager = self.namespace_manager.read().unwrap();
+        match query_type {
+            QueryType::Join => {
+                if !auth_manager.is_authorized(user_id, "join") {
+                    return Err(anyhow!("Unauthorized"));
+                }
+                let spec = JoinSpec::from_args(args)?;
+                let left = namespace_manager.get_namespace(&spec.left)?;
+                let right = namespace_manager.get_namespace(&spec.right)?;
+                let rows = operators::join(&left, &right, &spec)?;
+                Ok(serde_json::to_string(&rows)?)
+            }
+            QueryType::Aggregate => {
+                if !auth_manager.is_authorized(user_id, "aggregate") {
+                    return Err(anyhow!("Unauthorized"));
+                }
+                let spec = AggregateSpec::from_args(args)?;
+                let ns = namespace_manager.get_namespace(&spec.namespace)?;
+                let rows = operators::aggregate(&ns, &spec)?;
+                Ok(serde_json::to_string(&rows)?)
+            }
+            other => Err(anyhow!("{:?} has no command form", other)),
+        }
+    }
+
+    fn register_db_functions(&self, lua_ctx: &LuaContext, user_id: String, sink: Option<TokenSink>) -> Result<()> {
         let namespace_manager = self.namespace_manager.clone();
         let llm = self.llm.clone();
         let embedding = self.embedding.clone();
@@ -74,26 +148,31 @@ impl QueryExecutor {
         let llm_semaphore = self.llm_semaphore.clone();
         let embedding_semaphore = self.embedding_semaphore.clone();
         let lua_vm = self.lua_vm.clone();
+        let import_dir = self.import_dir.clone();
 
         let user_id = RefCell::new(user_id);
 
         // Namespace operations
-        lua_ctx.globals().set("create_namespace", lua_ctx.create_function_mut(move |_, (name, dimensions, metric, scalar): (String, usize, String, String)| {
+        lua_ctx.globals().set("create_namespace", lua_ctx.create_function_mut(move |_, (name, dimensions, metric, scalar, options): (String, usize, String, String, Option<LuaTable>)| {
             let user_id = user_id.borrow().clone();
             if !auth_manager.read().unwrap().is_authorized(&user_id, "create_namespace") {
                 return Err(LuaError::RuntimeError("Unauthorized".to_string()));
             }
-            let metric = match metric.as_str() {
-                "cosine" => MetricKind::Cos,
-                "euclidean" => MetricKind::L2sq,
-                _ => return Err(LuaError::RuntimeError("Invalid metric kind".to_string())),
-            };
-            let scalar = match scalar.as_str() {
-                "f32" => ScalarKind::F32,
-                "f16" => ScalarKind::F16,
-                _ => return Err(LuaError::RuntimeError("Invalid scalar kind".to_string())),
-            };
-            namespace_manager.write().unwrap().create_namespace(&name, dimensions, metric, scalar)
+            let metric = parse_metric(&metric)
+                .map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+            let scalar = parse_scalar(&scalar)
+                .map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+            let config = vector_config(dimensions, metric, scalar, options)?;
+            namespace_manager.write().unwrap().create_namespace_with_config(&name, config)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to create namespace: {}", e)))
+        })?)?;
+
+        lua_ctx.globals().set("create_geo_namespace", lua_ctx.create_function_mut(move |_, name: String| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "create_geo_namespace") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            namespace_manager.write().unwrap().create_geo_namespace(&name)
                 .map_err(|e| LuaError::RuntimeError(format!("Failed to create namespace: {}", e)))
         })?)?;
 
@@ -119,6 +198,46 @@ impl QueryExecutor {
             Ok(lua_namespaces)
         })?)?;
 
+        lua_ctx.globals().set("create_vector_field", lua_ctx.create_function_mut(move |_, (namespace, field, dimensions, metric, scalar, options): (String, String, usize, String, String, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "create_vector_field") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let metric = parse_metric(&metric)
+                .map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+            let scalar = parse_scalar(&scalar)
+                .map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+            let config = vector_config(dimensions, metric, scalar, options)?;
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            ns.create_vector_field(&field, config)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to create vector field: {}", e)))
+        })?)?;
+
+        lua_ctx.globals().set("list_vector_fields", lua_ctx.create_function_mut(move |lua_ctx, namespace: String| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "list_vector_fields") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            let lua_fields = lua_ctx.create_table()?;
+            for (i, (name, field)) in ns.vector_fields().into_iter().enumerate() {
+                lua_fields.set(i + 1, vector_field_table(lua_ctx, &name, &field)?)?;
+            }
+            Ok(lua_fields)
+        })?)?;
+
+        lua_ctx.globals().set("namespace_stats", lua_ctx.create_function_mut(move |lua_ctx, namespace: String| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "namespace_stats") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            namespace_stats_table(lua_ctx, &namespace, &ns)
+        })?)?;
+
         // Database operations
         lua_ctx.globals().set("select", lua_ctx.create_function_mut(move |_, (namespace, key): (String, String)| {
             let user_id = user_id.borrow().clone();
@@ -139,7 +258,7 @@ impl QueryExecutor {
             }
             let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
                 .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
-            ns.db.put(key.as_bytes(), value.as_bytes())
+            ns.put(&key, &value)
                 .map_err(|e| LuaError::RuntimeError(format!("Failed to insert value: {}", e)))?;
             Ok(())
         })?)?;
@@ -151,7 +270,7 @@ impl QueryExecutor {
             }
             let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
                 .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
-            ns.db.put(key.as_bytes(), value.as_bytes())
+            ns.put(&key, &value)
                 .map_err(|e| LuaError::RuntimeError(format!("Failed to update value: {}", e)))?;
             Ok(())
         })?)?;
@@ -163,10 +282,234 @@ impl QueryExecutor {
             }
             let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
                 .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
-            ns.db.delete(key.as_bytes())
+            ns.delete(&key)
                 .map_err(|e| LuaError::RuntimeError(format!("Failed to delete value: {}", e)))?;
             Ok(())
         })?)?;
+        // Schema operations
+        lua_ctx.globals().set("set_schema", lua_ctx.create_function_mut(move |_, (namespace, schema, options): (String, String, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "set_schema") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let schema = serde_json::from_str(&schema)
+                .map_err(|e| LuaError::RuntimeError(format!("Schema is not valid JSON: {}", e)))?;
+            let key_pattern = match options {
+                Some(options) => options.get::<_, Option<String>>("key_pattern")?,
+                None => None,
+            };
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            ns.schema.set(schema, key_pattern)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to set schema: {}", e)))
+        })?)?;
+
+        lua_ctx.globals().set("get_schema", lua_ctx.create_function_mut(move |_, (namespace, version): (String, Option<u32>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "get_schema") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            let definition = match version {
+                Some(version) => ns.schema.get(version)
+                    .map_err(|e| LuaError::RuntimeError(format!("Failed to get schema: {}", e)))?,
+                None => ns.schema.current(),
+            };
+            definition
+                .map(|d| serde_json::to_string(&d))
+                .transpose()
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to serialize schema: {}", e)))
+        })?)?;
+
+        lua_ctx.globals().set("validate_schema", lua_ctx.create_function_mut(move |lua_ctx, (namespace, schema, options): (String, String, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "validate_schema") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let schema = serde_json::from_str(&schema)
+                .map_err(|e| LuaError::RuntimeError(format!("Schema is not valid JSON: {}", e)))?;
+            let key_pattern = match options {
+                Some(options) => options.get::<_, Option<String>>("key_pattern")?,
+                None => None,
+            };
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            let violations = ns.schema.dry_run(schema, key_pattern)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to validate schema: {}", e)))?;
+
+            let lua_violations = lua_ctx.create_table()?;
+            for (i, violation) in violations.into_iter().enumerate() {
+                let violation_table = lua_ctx.create_table()?;
+                violation_table.set("key", violation.key)?;
+                violation_table.set("errors", violation.errors)?;
+                lua_violations.set(i + 1, violation_table)?;
+            }
+            Ok(lua_violations)
+        })?)?;
+
+        lua_ctx.globals().set("drop_schema", lua_ctx.create_function_mut(move |_, namespace: String| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "drop_schema") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            ns.schema.clear()
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to drop schema: {}", e)))
+        })?)?;
+
+        // Graph operations
+        lua_ctx.globals().set("link", lua_ctx.create_function_mut(move |_, (namespace, from, to, label, props): (String, String, String, String, Option<LuaValue>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "link") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let props = lua_to_json(props.unwrap_or(LuaValue::Nil))?;
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            ns.graph.link(&from, &to, &label, props)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to link keys: {}", e)))
+        })?)?;
+
+        lua_ctx.globals().set("unlink", lua_ctx.create_function_mut(move |_, (namespace, from, to, label): (String, String, String, Option<String>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "unlink") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            ns.graph.unlink(&from, &to, label.as_deref())
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to unlink keys: {}", e)))
+        })?)?;
+
+        lua_ctx.globals().set("neighbors", lua_ctx.create_function_mut(move |lua_ctx, (namespace, key, options): (String, String, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "neighbors") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let (label, direction, _, _) = graph_options(options)?;
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            let edges = ns.graph.neighbors(&key, label.as_deref(), direction)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to list neighbors: {}", e)))?;
+
+            let lua_edges = lua_ctx.create_table()?;
+            for (i, edge) in edges.into_iter().enumerate() {
+                let edge_table = lua_ctx.create_table()?;
+                let (neighbor, direction) = if edge.from == key { (edge.to, "out") } else { (edge.from, "in") };
+                edge_table.set("key", neighbor)?;
+                edge_table.set("label", edge.label)?;
+                edge_table.set("direction", direction)?;
+                edge_table.set("props", json_to_lua(lua_ctx, &edge.props)?)?;
+                lua_edges.set(i + 1, edge_table)?;
+            }
+            Ok(lua_edges)
+        })?)?;
+
+        lua_ctx.globals().set("traverse", lua_ctx.create_function_mut(move |lua_ctx, (namespace, start, options): (String, String, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "traverse") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let (label, direction, max_depth, limit) = graph_options(options)?;
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            let reached = ns.graph.traverse(&start, max_depth, label.as_deref(), direction, limit)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to traverse graph: {}", e)))?;
+
+            let lua_reached = lua_ctx.create_table()?;
+            for (i, (key, depth)) in reached.into_iter().enumerate() {
+                let node_table = lua_ctx.create_table()?;
+                node_table.set("key", key)?;
+                node_table.set("depth", depth)?;
+                lua_reached.set(i + 1, node_table)?;
+            }
+            Ok(lua_reached)
+        })?)?;
+
+        lua_ctx.globals().set("shortest_path", lua_ctx.create_function_mut(move |_, (namespace, from, to, options): (String, String, String, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "shortest_path") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let (label, direction, max_depth, _) = graph_options(options)?;
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            ns.graph.shortest_path(&from, &to, max_depth, label.as_deref(), direction)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to find path: {}", e)))
+        })?)?;
+
+        // Relational operations
+        lua_ctx.globals().set("join", lua_ctx.create_function_mut(move |lua_ctx, (left, right, options): (String, String, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "join") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let mut spec = JoinSpec::new(&left, &right, KEY_FIELD, KEY_FIELD, None);
+            if let Some(options) = options {
+                if let Some(on) = options.get::<_, Option<String>>("on")? {
+                    spec.left_on = on.clone();
+                    spec.right_on = on;
+                }
+                if let Some(left_on) = options.get::<_, Option<String>>("left_on")? {
+                    spec.left_on = left_on;
+                }
+                if let Some(right_on) = options.get::<_, Option<String>>("right_on")? {
+                    spec.right_on = right_on;
+                }
+                spec.limit = options.get::<_, Option<usize>>("limit")?;
+            }
+            let (left_ns, right_ns) = {
+                let manager = namespace_manager.read().unwrap();
+                let left_ns = manager.get_namespace(&spec.left)
+                    .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+                let right_ns = manager.get_namespace(&spec.right)
+                    .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+                (left_ns, right_ns)
+            };
+            let rows = operators::join(&left_ns, &right_ns, &spec)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to join namespaces: {}", e)))?;
+
+            let lua_rows = lua_ctx.create_table()?;
+            for (i, row) in rows.into_iter().enumerate() {
+                let row_table = lua_ctx.create_table()?;
+                row_table.set("left_key", row.left_key)?;
+                row_table.set("left_value", row.left_value)?;
+                row_table.set("right_key", row.right_key)?;
+                row_table.set("right_value", row.right_value)?;
+                lua_rows.set(i + 1, row_table)?;
+            }
+            Ok(lua_rows)
+        })?)?;
+
+        lua_ctx.globals().set("aggregate", lua_ctx.create_function_mut(move |lua_ctx, (namespace, function, options): (String, String, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "aggregate") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let function = AggregateFunction::parse(&function)
+                .map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+            let mut spec = AggregateSpec { namespace, function, field: None, group_by: None };
+            if let Some(options) = options {
+                spec.field = options.get::<_, Option<String>>("field")?;
+                spec.group_by = options.get::<_, Option<String>>("group_by")?;
+            }
+            let ns = namespace_manager.read().unwrap().get_namespace(&spec.namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            let rows = operators::aggregate(&ns, &spec)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to aggregate: {}", e)))?;
+
+            let lua_rows = lua_ctx.create_table()?;
+            for (i, row) in rows.into_iter().enumerate() {
+                let row_table = lua_ctx.create_table()?;
+                row_table.set("group", json_to_lua(lua_ctx, &row.group)?)?;
+                row_table.set("value", json_to_lua(lua_ctx, &row.value)?)?;
+                lua_rows.set(i + 1, row_table)?;
+            }
+            Ok(lua_rows)
+        })?)?;
+
         // Embedding operations
         lua_ctx.globals().set("generate_embedding", lua_ctx.create_function_mut(move |lua_ctx, texts: Vec<String>| {
             let user_id = user_id.borrow().clone();
@@ -192,17 +535,38 @@ impl QueryExecutor {
         })?)?;
 
         // LLM operations
-        lua_ctx.globals().set("llm_query", lua_ctx.create_function_mut(move |_, (prompt, sample_len, temp, repeat_penalty, repeat_last_n): (String, usize, f64, f32, usize)| {
+        // `options` is a generation options table, or just the maximum number
+        // of tokens. With `on_token`, each piece of text is passed to it as
+        // it is generated; returning false from it stops the generation
+        lua_ctx.globals().set("llm_query", lua_ctx.create_function_mut(move |lua_ctx, (prompt, args): (String, LuaMultiValue)| {
             let user_id = user_id.borrow().clone();
             if !auth_manager.read().unwrap().is_authorized(&user_id, "llm_query") {
                 return Err(LuaError::RuntimeError("Unauthorized".to_string()));
             }
+            let (options, on_token) = llm_query_args(lua_ctx, args)?;
+            let llm = llm.read().unwrap();
+            let config = generation_config(&llm, options)?;
             let permit = llm_semaphore.try_acquire()
                 .map_err(|e| LuaError::RuntimeError(format!("Failed to acquire LLM semaphore: {}", e)))?;
-            let llm_result = llm.read().unwrap().generate(&prompt, sample_len, temp, repeat_penalty, repeat_last_n)
-                .map_err(|e| LuaError::RuntimeError(format!("Failed to generate LLM response: {}", e)))?;
+            let mut callback_error = None;
+            let llm_result = llm.generate_stream(&prompt, &config, |delta| {
+                let mut keep_going = sink.as_ref().map_or(true, |sink| sink(delta));
+                if let Some(on_token) = &on_token {
+                    match on_token.call::<_, Option<bool>>(delta.to_string()) {
+                        Ok(verdict) => keep_going &= verdict != Some(false),
+                        Err(e) => {
+                            callback_error = Some(e);
+                            keep_going = false;
+                        }
+                    }
+                }
+                keep_going
+            }).map_err(|e| LuaError::RuntimeError(format!("Failed to generate LLM response: {}", e)))?;
             drop(permit);
-            Ok(llm_result)
+            match callback_error {
+                Some(e) => Err(e),
+                None => Ok(llm_result),
+            }
         })?)?;
 
         // File operations
@@ -228,26 +592,455 @@ impl QueryExecutor {
         })?)?;
 
         // Vector search operations
-        lua_ctx.globals().set("similarity_search", lua_ctx.create_function_mut(move |lua_ctx, (namespace, vector, k): (String, Vec<f32>, usize)| {
+        lua_ctx.globals().set("similarity_search", lua_ctx.create_function_mut(move |lua_ctx, (namespace, vector, k, options): (String, LuaValue, usize, Option<LuaTable>)| {
             let user_id = user_id.borrow().clone();
             if !auth_manager.read().unwrap().is_authorized(&user_id, "similarity_search") {
                 return Err(LuaError::RuntimeError("Unauthorized".to_string()));
             }
+            let search_options = vector_search_options(options)?;
             let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
                 .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
-            let results = ns.vector_db.search(&vector, k)
+            let field = ns.vector_field(search_options.field.as_deref())
+                .map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+            let vector = lua_vector(vector, &field.config)?;
+            let results = ns.search_vectors(&vector, k, &search_options)
                 .map_err(|e| LuaError::RuntimeError(format!("Failed to perform similarity search: {}", e)))?;
-            
+            vector_hits_table(lua_ctx, &ns, results)
+        })?)?;
+
+        lua_ctx.globals().set("similarity_search_batch", lua_ctx.create_function_mut(move |lua_ctx, (namespace, vectors, k, options): (String, Vec<LuaValue>, usize, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "similarity_search_batch") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let search_options = vector_search_options(options)?;
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            let field = ns.vector_field(search_options.field.as_deref())
+                .map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+            let vectors = vectors.into_iter()
+                .map(|vector| lua_vector(vector, &field.config))
+                .collect::<Result<Vec<_>, _>>()?;
+            let results = ns.search_vectors_batch(&vectors, k, &search_options)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to perform similarity search: {}", e)))?;
+
             let lua_results = lua_ctx.create_table()?;
-            for (i, (id, distance)) in results.into_iter().enumerate() {
+            for (i, hits) in results.into_iter().enumerate() {
+                lua_results.set(i + 1, vector_hits_table(lua_ctx, &ns, hits)?)?;
+            }
+            Ok(lua_results)
+        })?)?;
+
+        lua_ctx.globals().set("range_search", lua_ctx.create_function_mut(move |lua_ctx, (namespace, vector, max_distance, options): (String, LuaValue, f32, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "range_search") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let limit = match &options {
+                Some(options) => options.get::<_, Option<usize>>("limit")?.unwrap_or(usize::MAX),
+                None => usize::MAX,
+            };
+            let search_options = vector_search_options(options)?;
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            let field = ns.vector_field(search_options.field.as_deref())
+                .map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+            let vector = lua_vector(vector, &field.config)?;
+            let results = ns.range_search(&vector, max_distance, limit, &search_options)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to perform range search: {}", e)))?;
+            vector_hits_table(lua_ctx, &ns, results)
+        })?)?;
+
+        lua_ctx.globals().set("insert_with_embedding", lua_ctx.create_function_mut(move |_, (namespace, key, value, options): (String, String, String, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "insert_with_embedding") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let (mut vector, mut metadata, mut field, mut dedup_threshold, mut reject_duplicates) = (None, None, None, None, false);
+            if let Some(options) = options {
+                if let Some(value) = options.get::<_, Option<LuaValue>>("metadata")? {
+                    metadata = Some(lua_to_json(value)?);
+                }
+                vector = options.get::<_, Option<LuaValue>>("vector")?;
+                field = options.get::<_, Option<String>>("field")?;
+                dedup_threshold = options.get::<_, Option<f32>>("dedup_threshold")?;
+                reject_duplicates = match options.get::<_, Option<String>>("on_duplicate")?.as_deref() {
+                    None | Some("return") => false,
+                    Some("reject") => true,
+                    Some(other) => return Err(LuaError::RuntimeError(format!("Invalid on_duplicate '{}': use 'return' or 'reject'", other))),
+                };
+            }
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+
+            let vector = match vector {
+                Some(vector) => {
+                    let field = ns.vector_field(field.as_deref())
+                        .map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+                    lua_vector(vector, &field.config)?
+                }
+                None => {
+                    let permit = embedding_semaphore.try_acquire()
+                        .map_err(|e| LuaError::RuntimeError(format!("Failed to acquire embedding semaphore: {}", e)))?;
+                    let vector = embedding.read().unwrap().generate(vec![value.as_str()])
+                        .map_err(|e| LuaError::RuntimeError(format!("Failed to generate embeddings: {}", e)))?
+                        .remove(0);
+                    drop(permit);
+                    vector
+                }
+            };
+
+            let outcome = ns.insert_document(&key, &value, field.as_deref(), &vector, metadata.as_ref(), dedup_threshold)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to insert value: {}", e)))?;
+            match outcome {
+                InsertOutcome::Inserted(id) => Ok((id, None)),
+                InsertOutcome::Duplicate(duplicate) => {
+                    let existing = duplicate.key.unwrap_or_default();
+                    if reject_duplicates {
+                        return Err(LuaError::RuntimeError(format!("'{}' is a near-duplicate of '{}' (distance {})", key, existing, duplicate.distance)));
+                    }
+                    // The store is untouched; point the caller at the existing key
+                    Ok((duplicate.id, Some(existing)))
+                }
+            }
+        })?)?;
+
+        lua_ctx.globals().set("insert_chunks", lua_ctx.create_function_mut(move |_, (namespace, key, value, chunks, options): (String, String, String, Vec<LuaTable>, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "insert_chunks") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let field = match options {
+                Some(options) => options.get::<_, Option<String>>("field")?,
+                None => None,
+            };
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            let vector_field = ns.vector_field(field.as_deref())
+                .map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+
+            let mut spans = Vec::with_capacity(chunks.len());
+            for chunk in &chunks {
+                let start: usize = chunk.get("start")?;
+                let end: usize = chunk.get("end")?;
+                if start >= end || end > value.len() || !value.is_char_boundary(start) || !value.is_char_boundary(end) {
+                    return Err(LuaError::RuntimeError(format!("Invalid chunk span {}..{}", start, end)));
+                }
+                let vector = match chunk.get::<_, LuaValue>("vector")? {
+                    LuaValue::Nil => None,
+                    value => Some(lua_vector(value, &vector_field.config)?),
+                };
+                spans.push((start, end, vector));
+            }
+            // Embed every chunk that didn't bring its own vector in one batch
+            let texts: Vec<&str> = spans.iter()
+                .filter(|(_, _, vector)| vector.is_none())
+                .map(|(start, end, _)| &value[*start..*end])
+                .collect();
+            let mut generated = if texts.is_empty() {
+                Vec::new()
+            } else {
+                let permit = embedding_semaphore.try_acquire()
+                    .map_err(|e| LuaError::RuntimeError(format!("Failed to acquire embedding semaphore: {}", e)))?;
+                let vectors = embedding.read().unwrap().generate(texts)
+                    .map_err(|e| LuaError::RuntimeError(format!("Failed to generate embeddings: {}", e)))?;
+                drop(permit);
+                vectors
+            }.into_iter();
+            let chunks: Vec<Chunk> = spans.into_iter()
+                .map(|(start, end, vector)| Chunk { start, end, vector: vector.or_else(|| generated.next()).unwrap_or_default() })
+                .collect();
+
+            ns.insert_chunked_document(&key, &value, field.as_deref(), &chunks)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to insert chunks: {}", e)))
+        })?)?;
+
+        lua_ctx.globals().set("hybrid_search", lua_ctx.create_function_mut(move |lua_ctx, (namespace, text, options): (String, String, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "hybrid_search") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let mut hybrid_options = HybridOptions::default();
+            if let Some(options) = options {
+                if let Some(k) = options.get::<_, Option<usize>>("k")? {
+                    hybrid_options.k = k;
+                }
+                if let Some(alpha) = options.get::<_, Option<f32>>("alpha")? {
+                    if !(0.0..=1.0).contains(&alpha) {
+                        return Err(LuaError::RuntimeError("alpha must be between 0 and 1".to_string()));
+                    }
+                    hybrid_options.alpha = alpha;
+                }
+                if let Some(fusion) = options.get::<_, Option<String>>("fusion")? {
+                    hybrid_options.fusion = FusionMethod::parse(&fusion)
+                        .map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+                }
+                hybrid_options.field = options.get::<_, Option<String>>("field")?;
+            }
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+
+            let permit = embedding_semaphore.try_acquire()
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to acquire embedding semaphore: {}", e)))?;
+            let query_vector = embedding.read().unwrap().generate(vec![text.as_str()])
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to generate embeddings: {}", e)))?
+                .remove(0);
+            drop(permit);
+
+            let results = hybrid_search(&ns, &text, &query_vector, &hybrid_options)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to perform hybrid search: {}", e)))?;
+
+            let lua_results = lua_ctx.create_table()?;
+            for (i, hit) in results.into_iter().enumerate() {
+                let result_table = lua_ctx.create_table()?;
+                result_table.set("key", hit.key)?;
+                result_table.set("value", hit.value)?;
+                result_table.set("score", hit.score)?;
+                result_table.set("vector_distance", hit.vector_distance)?;
+                result_table.set("text_score", hit.text_score)?;
+                lua_results.set(i + 1, result_table)?;
+            }
+            Ok(lua_results)
+        })?)?;
+
+        lua_ctx.globals().set("reserve", lua_ctx.create_function_mut(move |_, (namespace, capacity, options): (String, usize, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "reserve") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let field = match options {
+                Some(options) => options.get::<_, Option<String>>("field")?,
+                None => None,
+            };
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            let field = ns.vector_field(field.as_deref())
+                .map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+            field.index.reserve(capacity)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to reserve capacity: {}", e)))
+        })?)?;
+
+        lua_ctx.globals().set("import_vectors", lua_ctx.create_function_mut(move |_, (namespace, path, options): (String, String, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "import_vectors") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let (field, keys) = match options {
+                Some(options) => (options.get::<_, Option<String>>("field")?, options.get::<_, Option<String>>("keys")?),
+                None => (None, None),
+            };
+            let import_dir = import_dir.as_deref()
+                .ok_or_else(|| LuaError::RuntimeError("Importing files is disabled; start with --import-dir".to_string()))?;
+            let resolve = |path: &str| resolve_within(import_dir, Path::new(path))
+                .map_err(|e| LuaError::RuntimeError(format!("Invalid import path: {:#}", e)));
+            let path = resolve(&path)?;
+            let keys = keys.as_deref().map(resolve).transpose()?;
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            let (keys, vectors) = read_dataset(&path, keys.as_deref())
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to read vectors: {:#}", e)))?;
+            ns.import_vectors(field.as_deref(), &keys, vectors)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to import vectors: {:#}", e)))
+        })?)?;
+
+        lua_ctx.globals().set("rebuild_vector_index", lua_ctx.create_function_mut(move |_, (namespace, options): (String, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "rebuild_vector_index") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            let field = match &options {
+                Some(options) => options.get::<_, Option<String>>("field")?,
+                None => None,
+            };
+            let name = field.unwrap_or_else(|| DEFAULT_VECTOR_FIELD.to_string());
+            let mut config = ns.vector_field(Some(&name))
+                .map_err(|e| LuaError::RuntimeError(e.to_string()))?
+                .config;
+            if let Some(options) = options {
+                if let Some(scalar) = options.get::<_, Option<String>>("scalar")? {
+                    config.quantization = parse_scalar(&scalar).map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+                }
+                if let Some(connectivity) = options.get::<_, Option<usize>>("connectivity")? {
+                    config.connectivity = connectivity;
+                }
+                if let Some(expansion_add) = options.get::<_, Option<usize>>("expansion_add")? {
+                    config.expansion_add = expansion_add;
+                }
+                if let Some(expansion_search) = options.get::<_, Option<usize>>("expansion_search")? {
+                    config.expansion_search = expansion_search;
+                }
+            }
+            ns.rebuild_vector_field(&name, config)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to rebuild vector index: {}", e)))
+        })?)?;
+
+        lua_ctx.globals().set("set_vector_transforms", lua_ctx.create_function_mut(move |_, (namespace, steps, options): (String, LuaTable, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "set_vector_transforms") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let (field, sample) = match options {
+                Some(options) => (
+                    options.get::<_, Option<String>>("field")?,
+                    options.get::<_, Option<usize>>("sample")?.unwrap_or(10_000),
+                ),
+                None => (None, 10_000),
+            };
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            let mut transforms = Vec::new();
+            for step in steps.sequence_values::<LuaTable>() {
+                let step = step?;
+                let kind: String = step.get("type")?;
+                let transform = match kind.as_str() {
+                    "normalize" => VectorTransform::Normalize,
+                    "truncate" => VectorTransform::Truncate { dimensions: step.get("dimensions")? },
+                    // Fitted on stored vectors run through the steps before it
+                    "pca" => VectorTransform::Pca(ns.fit_pca(field.as_deref(), &transforms, step.get("dimensions")?, sample)
+                        .map_err(|e| LuaError::RuntimeError(format!("Failed to fit PCA: {}", e)))?),
+                    _ => return Err(LuaError::RuntimeError(format!("Unknown vector transform '{}'", kind))),
+                };
+                transforms.push(transform);
+            }
+            ns.set_vector_transforms(field.as_deref(), transforms)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to set vector transforms: {}", e)))
+        })?)?;
+
+        lua_ctx.globals().set("geo_add", lua_ctx.create_function_mut(move |_, (namespace, key, lat, lon, options): (String, String, f64, f64, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "geo_add") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let field = match options {
+                Some(options) => options.get::<_, Option<String>>("field")?,
+                None => None,
+            };
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            ns.geo_add(&key, field.as_deref(), lat, lon)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to add point: {}", e)))
+        })?)?;
+
+        lua_ctx.globals().set("geo_near", lua_ctx.create_function_mut(move |lua_ctx, (namespace, lat, lon, k, options): (String, f64, f64, usize, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "geo_near") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let (mut field, mut max_km, mut bbox) = (None, None, None);
+            if let Some(options) = options {
+                field = options.get::<_, Option<String>>("field")?;
+                max_km = options.get::<_, Option<f32>>("max_km")?;
+                if let Some(corners) = options.get::<_, Option<LuaTable>>("bbox")? {
+                    bbox = Some(BoundingBox::new(
+                        corners.get("min_lat")?,
+                        corners.get("min_lon")?,
+                        corners.get("max_lat")?,
+                        corners.get("max_lon")?,
+                    ).map_err(|e| LuaError::RuntimeError(e.to_string()))?);
+                }
+            }
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            let hits = ns.geo_near(field.as_deref(), lat, lon, k, max_km, bbox)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to perform geo search: {}", e)))?;
+
+            let lua_results = lua_ctx.create_table()?;
+            for (i, hit) in hits.into_iter().enumerate() {
                 let result_table = lua_ctx.create_table()?;
-                result_table.set("id", id)?;
-                result_table.set("distance", distance)?;
+                result_table.set("id", hit.id)?;
+                result_table.set("key", hit.key)?;
+                result_table.set("distance_km", hit.distance_km)?;
                 lua_results.set(i + 1, result_table)?;
             }
             Ok(lua_results)
         })?)?;
 
+        lua_ctx.globals().set("find_duplicates", lua_ctx.create_function_mut(move |lua_ctx, (namespace, threshold, options): (String, f32, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "find_duplicates") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let field = match options {
+                Some(options) => options.get::<_, Option<String>>("field")?,
+                None => None,
+            };
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            let groups = ns.find_duplicates(field.as_deref(), threshold)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to find duplicates: {}", e)))?;
+
+            let lua_groups = lua_ctx.create_table()?;
+            for (i, keys) in groups.into_iter().enumerate() {
+                lua_groups.set(i + 1, keys)?;
+            }
+            Ok(lua_groups)
+        })?)?;
+
+        lua_ctx.globals().set("cluster", lua_ctx.create_function_mut(move |lua_ctx, (namespace, k, options): (String, usize, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "cluster") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let (field, iterations, sample) = match options {
+                Some(options) => (
+                    options.get::<_, Option<String>>("field")?,
+                    options.get::<_, Option<usize>>("iterations")?.unwrap_or(25),
+                    options.get::<_, Option<usize>>("sample")?,
+                ),
+                None => (None, 25, None),
+            };
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            let clusters = ns.cluster_vectors(field.as_deref(), k, iterations, sample)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to cluster vectors: {}", e)))?;
+
+            let lua_clusters = lua_ctx.create_table()?;
+            for (i, cluster) in clusters.into_iter().enumerate() {
+                let cluster_table = lua_ctx.create_table()?;
+                cluster_table.set("id", cluster.id)?;
+                cluster_table.set("size", cluster.size)?;
+                cluster_table.set("representative", cluster.representative)?;
+                lua_clusters.set(i + 1, cluster_table)?;
+            }
+            Ok(lua_clusters)
+        })?)?;
+
+        lua_ctx.globals().set("update_vector", lua_ctx.create_function_mut(move |_, (namespace, key, vector, options): (String, String, LuaValue, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "update_vector") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            let field = match options {
+                Some(options) => options.get::<_, Option<String>>("field")?,
+                None => None,
+            };
+            let config = ns.vector_field(field.as_deref())
+                .map_err(|e| LuaError::RuntimeError(e.to_string()))?
+                .config;
+            let vector = lua_vector(vector, &config)?;
+            ns.add_vector(&key, field.as_deref(), &vector)
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to update vector: {}", e)))
+        })?)?;
+
+        lua_ctx.globals().set("delete_vector", lua_ctx.create_function_mut(move |_, (namespace, key, options): (String, String, Option<LuaTable>)| {
+            let user_id = user_id.borrow().clone();
+            if !auth_manager.read().unwrap().is_authorized(&user_id, "delete_vector") {
+                return Err(LuaError::RuntimeError("Unauthorized".to_string()));
+            }
+            let ns = namespace_manager.read().unwrap().get_namespace(&namespace)
+                .map_err(|e| LuaError::RuntimeError(format!("Namespace error: {}", e)))?;
+            let field = match options {
+                Some(options) => options.get::<_, Option<String>>("field")?,
+                None => None,
+            };
+            ns.remove_vector(&key, field.as_deref())
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to delete vector: {}", e)))
+        })?)?;
+
         // LuaRocks package management
         lua_ctx.globals().set("install_package", lua_ctx.create_function_mut(move |_, package_name: String| {
             let user_id = user_id.borrow().clone();
@@ -275,4 +1068,337 @@ impl QueryExecutor {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Reads a vector given either as a table of numbers or, for binary fields,
+/// as a string of bit-packed bytes.
+fn lua_vector(value: LuaValue, config: &VectorConfig) -> Result<Vec<f32>, LuaError> {
+    match value {
+        LuaValue::Table(table) => table.sequence_values::<f32>().collect(),
+        LuaValue::String(bytes) if config.quantization == ScalarKind::B1 => {
+            unpack_bits(bytes.as_bytes(), config.dimensions).map_err(|e| LuaError::RuntimeError(e.to_string()))
+        }
+        _ => Err(LuaError::RuntimeError("Expected a vector as a table of numbers".to_string())),
+    }
+}
+
+/// Reads `{field=, filter=, aggregate=, expansion_search=, exact=,
+/// diversity=, fetch_k=}` from a similarity search options table.
+fn vector_search_options(options: Option<LuaTable>) -> Result<VectorSearchOptions, LuaError> {
+    let mut search_options = VectorSearchOptions::default();
+    if let Some(options) = options {
+        search_options.field = options.get::<_, Option<String>>("field")?;
+        search_options.expansion_search = options.get::<_, Option<usize>>("expansion_search")?;
+        search_options.exact = options.get::<_, Option<bool>>("exact")?.unwrap_or(false);
+        if let Some(aggregate) = options.get::<_, Option<String>>("aggregate")? {
+            search_options.aggregation = ChunkAggregation::parse(&aggregate)
+                .map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+        }
+        if let Some(filter) = options.get::<_, Option<String>>("filter")? {
+            search_options.filter = Some(FilterExpr::parse(&filter)
+                .map_err(|e| LuaError::RuntimeError(format!("Invalid filter: {}", e)))?);
+        }
+        if let Some(diversity) = options.get::<_, Option<f32>>("diversity")? {
+            let fetch_k = options.get::<_, Option<usize>>("fetch_k")?;
+            search_options.mmr = Some(MmrOptions::new(diversity, fetch_k)
+                .map_err(|e| LuaError::RuntimeError(e.to_string()))?);
+        }
+    }
+    Ok(search_options)
+}
+
+/// Converts vector hits into a list of `{id, key, value, distance, chunks}` tables.
+fn vector_hits_table<'lua>(lua_ctx: LuaContext<'lua>, ns: &Namespace, hits: Vec<VectorHit>) -> Result<LuaTable<'lua>, LuaError> {
+    let lua_results = lua_ctx.create_table()?;
+    for (i, hit) in hits.into_iter().enumerate() {
+        let value = match &hit.key {
+            Some(key) => ns.db.get(key.as_bytes())
+                .map_err(|e| LuaError::RuntimeError(format!("Failed to retrieve value: {}", e)))?
+                .map(|v| String::from_utf8_lossy(&v).into_owned()),
+            None => None,
+        };
+        let result_table = lua_ctx.create_table()?;
+        result_table.set("id", hit.id)?;
+        result_table.set("key", hit.key)?;
+        result_table.set("value", value)?;
+        result_table.set("distance", hit.distance)?;
+        if !hit.chunks.is_empty() {
+            let chunks_table = lua_ctx.create_table()?;
+            for (j, chunk) in hit.chunks.into_iter().enumerate() {
+                let chunk_table = lua_ctx.create_table()?;
+                chunk_table.set("chunk", chunk.chunk + 1)?;
+                chunk_table.set("start", chunk.start)?;
+                chunk_table.set("end", chunk.end)?;
+                chunk_table.set("distance", chunk.distance)?;
+                chunks_table.set(j + 1, chunk_table)?;
+            }
+            result_table.set("chunks", chunks_table)?;
+        }
+        lua_results.set(i + 1, result_table)?;
+    }
+    Ok(lua_results)
+}
+
+/// Builds a vector configuration, reading `{multi=, connectivity=,
+/// expansion_add=, expansion_search=}` from an options table.
+fn vector_config(dimensions: usize, metric: MetricKind, scalar: ScalarKind, options: Option<LuaTable>) -> Result<VectorConfig, LuaError> {
+    let mut config = VectorConfig::new(dimensions, metric, scalar);
+    if let Some(options) = options {
+        config.multi = options.get::<_, Option<bool>>("multi")?.unwrap_or(false);
+        config.connectivity = options.get::<_, Option<usize>>("connectivity")?.unwrap_or(0);
+        config.expansion_add = options.get::<_, Option<usize>>("expansion_add")?.unwrap_or(0);
+        config.expansion_search = options.get::<_, Option<usize>>("expansion_search")?.unwrap_or(0);
+    }
+    Ok(config)
+}
+
+/// Summarizes a namespace and each of its vector fields.
+fn namespace_stats_table<'lua>(lua_ctx: LuaContext<'lua>, name: &str, ns: &Namespace) -> Result<LuaTable<'lua>, LuaError> {
+    let stats = lua_ctx.create_table()?;
+    stats.set("name", name)?;
+    let lua_fields = lua_ctx.create_table()?;
+    let mut vectors = 0;
+    let mut memory_usage = 0;
+    for (name, field) in ns.vector_fields() {
+        vectors += field.index.size();
+        memory_usage += field.index.memory_usage();
+        lua_fields.set(name.clone(), vector_field_table(lua_ctx, &name, &field)?)?;
+    }
+    stats.set("vectors", vectors)?;
+    stats.set("memory_usage", memory_usage)?;
+    stats.set("vector_fields", lua_fields)?;
+    Ok(stats)
+}
+
+/// Describes a vector field, reporting the HNSW parameters usearch resolved.
+fn vector_field_table<'lua>(lua_ctx: LuaContext<'lua>, name: &str, field: &VectorField) -> Result<LuaTable<'lua>, LuaError> {
+    let table = lua_ctx.create_table()?;
+    table.set("name", name)?;
+    table.set("dimensions", field.config.dimensions)?;
+    table.set("metric", metric_name(field.config.metric))?;
+    table.set("scalar", scalar_name(field.config.quantization))?;
+    table.set("multi", field.config.multi)?;
+    table.set("size", field.index.size())?;
+    table.set("capacity", field.index.capacity())?;
+    table.set("connectivity", field.index.connectivity())?;
+    table.set("expansion_add", field.index.expansion_add())?;
+    table.set("expansion_search", field.index.expansion_search())?;
+    table.set("memory_usage", field.index.memory_usage())?;
+    let transforms = lua_ctx.create_table()?;
+    for (i, transform) in field.config.transforms.iter().enumerate() {
+        let step = lua_ctx.create_table()?;
+        match transform {
+            VectorTransform::Normalize => step.set("type", "normalize")?,
+            VectorTransform::Truncate { dimensions } => {
+                step.set("type", "truncate")?;
+                step.set("dimensions", *dimensions)?;
+            }
+            VectorTransform::Pca(pca) => {
+                step.set("type", "pca")?;
+                step.set("dimensions", pca.components.len())?;
+            }
+        }
+        transforms.set(i + 1, step)?;
+    }
+    table.set("transforms", transforms)?;
+    Ok(table)
+}
+
+/// Splits the arguments of `llm_query` after the prompt into its options and
+/// `on_token` callback. Besides `(options, on_token)` this accepts the
+/// positional `(sample_len, temperature, repeat_penalty, repeat_last_n)`
+/// form, which has no callback. A number right after the prompt selects the
+/// positional form, where `nil` skips a parameter.
+fn llm_query_args<'lua>(lua_ctx: LuaContext<'lua>, args: LuaMultiValue<'lua>) -> Result<(Option<LuaValue<'lua>>, Option<LuaFunction<'lua>>), LuaError> {
+    let args = args.into_vec();
+    if matches!(args.first(), Some(LuaValue::Integer(_) | LuaValue::Number(_))) {
+        let names = ["max_tokens", "temperature", "repeat_penalty", "repeat_last_n"];
+        if args.len() > names.len() {
+            return Err(LuaError::RuntimeError("llm_query takes at most 4 sampling parameters after the prompt".to_string()));
+        }
+        let options = lua_ctx.create_table()?;
+        for (name, value) in names.iter().zip(args) {
+            match value {
+                LuaValue::Nil => {}
+                LuaValue::Integer(_) | LuaValue::Number(_) => options.set(*name, value)?,
+                _ => return Err(LuaError::RuntimeError(format!("{} must be a number", name))),
+            }
+        }
+        return Ok((Some(LuaValue::Table(options)), None));
+    }
+    if args.len() > 2 {
+        return Err(LuaError::RuntimeError("llm_query takes a prompt, options and an on_token callback".to_string()));
+    }
+    let mut args = args.into_iter();
+    let options = args.next();
+    let on_token = match args.next() {
+        None | Some(LuaValue::Nil) => None,
+        Some(LuaValue::Function(on_token)) => Some(on_token),
+        Some(_) => return Err(LuaError::RuntimeError("on_token must be a function".to_string())),
+    };
+    Ok((options, on_token))
+}
+
+/// Reads the options of `llm_query`: `{max_tokens=, temperature=, top_k=,
+/// top_p=, min_p=, seed=, stop=, repeat_penalty=, repeat_last_n=,
+/// logit_bias=, banned_tokens=}`. Tokens in `logit_bias` and `banned_tokens`
+/// are ids or vocabulary entries.
+fn generation_config(llm: &LLMWrapper, options: Option<LuaValue>) -> Result<GenerationConfig, LuaError> {
+    let mut config = GenerationConfig::default();
+    let options = match options {
+        None | Some(LuaValue::Nil) => return Ok(config),
+        Some(LuaValue::Table(options)) => options,
+        Some(_) => return Err(LuaError::RuntimeError("llm_query options must be a table or a token count".to_string())),
+    };
+    let token = |value: LuaValue| -> Result<u32, LuaError> {
+        match value {
+            LuaValue::Integer(id) => u32::try_from(id).map_err(|_| LuaError::RuntimeError(format!("Invalid token id {}", id))),
+            LuaValue::String(text) => {
+                let text = text.to_str()?;
+                llm.token_id(text).ok_or_else(|| LuaError::RuntimeError(format!("Unknown token '{}'", text)))
+            }
+            _ => Err(LuaError::RuntimeError("Tokens must be ids or strings".to_string())),
+        }
+    };
+
+    if let Some(max_tokens) = options.get::<_, Option<usize>>("max_tokens")? {
+        config.max_tokens = max_tokens;
+    }
+    if let Some(temperature) = options.get::<_, Option<f64>>("temperature")? {
+        config.temperature = temperature;
+    }
+    config.top_k = options.get("top_k")?;
+    config.top_p = options.get("top_p")?;
+    config.min_p = options.get("min_p")?;
+    config.seed = options.get("seed")?;
+    config.stop = match options.get::<_, LuaValue>("stop")? {
+        LuaValue::Nil => Vec::new(),
+        LuaValue::String(stop) => vec![stop.to_str()?.to_string()],
+        LuaValue::Table(stops) => stops.sequence_values::<String>().collect::<Result<_, _>>()?,
+        _ => return Err(LuaError::RuntimeError("stop must be a string or a list of strings".to_string())),
+    };
+    if let Some(repeat_penalty) = options.get::<_, Option<f32>>("repeat_penalty")? {
+        config.repeat_penalty = repeat_penalty;
+    }
+    if let Some(repeat_last_n) = options.get::<_, Option<usize>>("repeat_last_n")? {
+        config.repeat_last_n = repeat_last_n;
+    }
+    if let Some(bias) = options.get::<_, Option<LuaTable>>("logit_bias")? {
+        for pair in bias.pairs::<LuaValue, f32>() {
+            let (key, value) = pair?;
+            config.logit_bias.insert(token(key)?, value);
+        }
+    }
+    if let Some(banned) = options.get::<_, Option<LuaTable>>("banned_tokens")? {
+        for value in banned.sequence_values::<LuaValue>() {
+            config.banned_tokens.push(token(value?)?);
+        }
+    }
+    config.validate().map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+    Ok(config)
+}
+
+/// Reads `{label=, direction=, max_depth=, limit=}` from a graph options table.
+fn graph_options(options: Option<LuaTable>) -> Result<(Option<String>, Direction, usize, usize), LuaError> {
+    let mut label = None;
+    let mut direction = Direction::Outgoing;
+    let mut max_depth = 3;
+    let mut limit = 1000;
+    if let Some(options) = options {
+        label = options.get::<_, Option<String>>("label")?;
+        if let Some(name) = options.get::<_, Option<String>>("direction")? {
+            direction = Direction::parse(&name).map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+        }
+        if let Some(depth) = options.get::<_, Option<usize>>("max_depth")? {
+            max_depth = depth;
+        }
+        if let Some(max) = options.get::<_, Option<usize>>("limit")? {
+            limit = max;
+        }
+    }
+    Ok((label, direction, max_depth, limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::IndexLoadMode;
+    use crate::core::testing::temp_path;
+
+    #[test]
+    fn test_hnsw_config_roundtrip() {
+        let path = temp_path();
+        Lua::new().context(|ctx| {
+            let options = ctx.create_table().unwrap();
+            options.set("connectivity", 12).unwrap();
+            options.set("expansion_add", 96).unwrap();
+            options.set("expansion_search", 48).unwrap();
+            let config = vector_config(4, MetricKind::Cos, ScalarKind::F32, Some(options)).unwrap();
+            let ns = Namespace::create(path.clone(), config).unwrap();
+            ns.add_vector("a", None, &[1.0, 0.0, 0.0, 0.0]).unwrap();
+            ns.save_vectors().unwrap();
+            drop(ns);
+
+            let ns = Namespace::open(path.clone(), IndexLoadMode::Load).unwrap();
+            let stats = namespace_stats_table(ctx, "fast", &ns).unwrap();
+            assert_eq!(stats.get::<_, String>("name").unwrap(), "fast");
+            assert_eq!(stats.get::<_, usize>("vectors").unwrap(), 1);
+            let fields: LuaTable = stats.get("vector_fields").unwrap();
+            let field: LuaTable = fields.get(DEFAULT_VECTOR_FIELD).unwrap();
+            assert_eq!(field.get::<_, usize>("dimensions").unwrap(), 4);
+            assert_eq!(field.get::<_, String>("metric").unwrap(), "cosine");
+            assert_eq!(field.get::<_, usize>("size").unwrap(), 1);
+            assert_eq!(field.get::<_, usize>("connectivity").unwrap(), 12);
+            assert_eq!(field.get::<_, usize>("expansion_add").unwrap(), 96);
+            assert_eq!(field.get::<_, usize>("expansion_search").unwrap(), 48);
+        });
+    }
+
+    #[test]
+    fn test_llm_query_args() {
+        Lua::new().context(|ctx| {
+            let args = |source: &str| ctx.load(source).eval::<LuaMultiValue>().unwrap();
+
+            let (options, on_token) = llm_query_args(ctx, args("return 100, 0.8, 1.1, 64")).unwrap();
+            let options = match options {
+                Some(LuaValue::Table(options)) => options,
+                _ => panic!("positional arguments should become an options table"),
+            };
+            assert!(on_token.is_none());
+            assert_eq!(options.get::<_, usize>("max_tokens").unwrap(), 100);
+            assert_eq!(options.get::<_, f64>("temperature").unwrap(), 0.8);
+            assert_eq!(options.get::<_, f32>("repeat_penalty").unwrap(), 1.1);
+            assert_eq!(options.get::<_, usize>("repeat_last_n").unwrap(), 64);
+
+            let (options, on_token) = llm_query_args(ctx, args("return {max_tokens = 5}, function() end")).unwrap();
+            assert!(matches!(options, Some(LuaValue::Table(_))));
+            assert!(on_token.is_some());
+            let (options, on_token) = llm_query_args(ctx, args("return 20")).unwrap();
+            match options {
+                Some(LuaValue::Table(options)) => assert_eq!(options.get::<_, usize>("max_tokens").unwrap(), 20),
+                _ => panic!("a bare number should become an options table"),
+            }
+            assert!(on_token.is_none());
+            assert!(matches!(llm_query_args(ctx, args("return")).unwrap(), (None, None)));
+
+            // nil skips a positional parameter
+            let (options, _) = llm_query_args(ctx, args("return 100, nil, 1.1")).unwrap();
+            let options = match options {
+                Some(LuaValue::Table(options)) => options,
+                _ => panic!("positional arguments should become an options table"),
+            };
+            assert_eq!(options.get::<_, usize>("max_tokens").unwrap(), 100);
+            assert!(options.get::<_, Option<f64>>("temperature").unwrap().is_none());
+            assert_eq!(options.get::<_, f32>("repeat_penalty").unwrap(), 1.1);
+            let (options, on_token) = llm_query_args(ctx, args("return nil, function() end")).unwrap();
+            assert!(matches!(options, Some(LuaValue::Nil)));
+            assert!(on_token.is_some());
+
+            assert!(llm_query_args(ctx, args("return 1, 0.5, 1.0, 64, 9")).is_err());
+            assert!(llm_query_args(ctx, args("return 1, 'hot'")).is_err());
+            assert!(llm_query_args(ctx, args("return {}, 'not a function'")).is_err());
+            // An options table is never read positionally
+            assert!(llm_query_args(ctx, args("return {max_tokens = 5}, 0.7")).is_err());
+        });
+    }
+}
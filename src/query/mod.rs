@@ -1,5 +1,6 @@
 pub mod executor;
 pub mod parser;
+pub mod operators;
 
-pub use executor::QueryExecutor;
+pub use executor::{QueryExecutor, TokenSink};
 pub use parser::QueryParser;
\ No newline at end of file
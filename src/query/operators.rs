@@ -0,0 +1,375 @@
+use std::collections::{BTreeMap, HashMap};
+use crate::core::Namespace;
+use crate::vector::json_path;
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+/// Field reference meaning "the key itself" in join and aggregate specs.
+pub const KEY_FIELD: &str = "_key";
+/// Field reference meaning "the whole value" in join and aggregate specs.
+pub const VALUE_FIELD: &str = "_value";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JoinSpec {
+    pub left: String,
+    pub right: String,
+    pub left_on: String,
+    pub right_on: String,
+    pub limit: Option<usize>,
+}
+
+impl JoinSpec {
+    /// Parses `<left> <right> [<left_on> [<right_on>]] [limit <n>]`.
+    pub fn from_args(args: &[String]) -> Result<Self> {
+        let (args, limit) = split_limit(args)?;
+        match args {
+            [left, right] => Ok(Self::new(left, right, KEY_FIELD, KEY_FIELD, limit)),
+            [left, right, on] => Ok(Self::new(left, right, on, on, limit)),
+            [left, right, left_on, right_on] => Ok(Self::new(left, right, left_on, right_on, limit)),
+            _ => Err(anyhow!("Usage: join <left> <right> [<left_on> [<right_on>]] [limit <n>]")),
+        }
+    }
+
+    pub fn new(left: &str, right: &str, left_on: &str, right_on: &str, limit: Option<usize>) -> Self {
+        Self {
+            left: left.to_string(),
+            right: right.to_string(),
+            left_on: left_on.to_string(),
+            right_on: right_on.to_string(),
+            limit,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Distinct,
+}
+
+impl AggregateFunction {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.to_lowercase().as_str() {
+            "count" => Ok(AggregateFunction::Count),
+            "sum" => Ok(AggregateFunction::Sum),
+            "avg" => Ok(AggregateFunction::Avg),
+            "min" => Ok(AggregateFunction::Min),
+            "max" => Ok(AggregateFunction::Max),
+            "distinct" => Ok(AggregateFunction::Distinct),
+            _ => Err(anyhow!("Invalid aggregate function '{}'", name)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregateSpec {
+    pub namespace: String,
+    pub function: AggregateFunction,
+    pub field: Option<String>,
+    pub group_by: Option<String>,
+}
+
+impl AggregateSpec {
+    /// Parses `<namespace> <function> [<field>] [by <group_field>]`.
+    pub fn from_args(args: &[String]) -> Result<Self> {
+        let usage = || anyhow!("Usage: aggregate <namespace> <function> [<field>] [by <group_field>]");
+        let (namespace, function, rest) = match args {
+            [namespace, function, rest @ ..] => (namespace, AggregateFunction::parse(function)?, rest),
+            _ => return Err(usage()),
+        };
+        let (field, group_by) = match rest {
+            [] => (None, None),
+            [field] => (Some(field.clone()), None),
+            [by, group] if by.eq_ignore_ascii_case("by") => (None, Some(group.clone())),
+            [field, by, group] if by.eq_ignore_ascii_case("by") => (Some(field.clone()), Some(group.clone())),
+            _ => return Err(usage()),
+        };
+        Ok(Self { namespace: namespace.clone(), function, field, group_by })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct JoinRow {
+    pub left_key: String,
+    pub left_value: String,
+    pub right_key: String,
+    pub right_value: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AggregateRow {
+    pub group: JsonValue,
+    pub value: JsonValue,
+}
+
+pub fn join(left: &Namespace, right: &Namespace, spec: &JoinSpec) -> Result<Vec<JoinRow>> {
+    let left_rows = decode_rows(left.db.scan()?);
+    let limit = spec.limit.unwrap_or(usize::MAX);
+
+    // Key-to-key joins can use point lookups instead of scanning the right side
+    if spec.right_on == KEY_FIELD {
+        let mut rows = Vec::new();
+        for (left_key, left_value) in left_rows {
+            let join_key = match field_value(&left_key, &left_value, &spec.left_on).and_then(|v| group_key(&v)) {
+                Some(join_key) => join_key,
+                None => continue,
+            };
+            if let Some(right_value) = right.db.get(join_key.as_bytes())? {
+                rows.push(JoinRow {
+                    left_key,
+                    left_value,
+                    right_key: join_key,
+                    right_value: String::from_utf8_lossy(&right_value).into_owned(),
+                });
+                if rows.len() >= limit {
+                    break;
+                }
+            }
+        }
+        return Ok(rows);
+    }
+
+    let right_rows = decode_rows(right.db.scan()?);
+    Ok(join_rows(&left_rows, &right_rows, &spec.left_on, &spec.right_on, limit))
+}
+
+/// Hash join of two row sets on the given field references.
+pub fn join_rows(
+    left: &[(String, String)],
+    right: &[(String, String)],
+    left_on: &str,
+    right_on: &str,
+    limit: usize,
+) -> Vec<JoinRow> {
+    let mut table: HashMap<String, Vec<&(String, String)>> = HashMap::new();
+    for row in right {
+        if let Some(join_key) = field_value(&row.0, &row.1, right_on).and_then(|v| group_key(&v)) {
+            table.entry(join_key).or_default().push(row);
+        }
+    }
+
+    let mut rows = Vec::new();
+    for (left_key, left_value) in left {
+        let join_key = match field_value(left_key, left_value, left_on).and_then(|v| group_key(&v)) {
+            Some(join_key) => join_key,
+            None => continue,
+        };
+        for (right_key, right_value) in table.get(&join_key).into_iter().flatten() {
+            rows.push(JoinRow {
+                left_key: left_key.clone(),
+                left_value: left_value.clone(),
+                right_key: right_key.clone(),
+                right_value: right_value.clone(),
+            });
+            if rows.len() >= limit {
+                return rows;
+            }
+        }
+    }
+    rows
+}
+
+pub fn aggregate(namespace: &Namespace, spec: &AggregateSpec) -> Result<Vec<AggregateRow>> {
+    let rows = decode_rows(namespace.db.scan()?);
+    aggregate_rows(&rows, spec.function, spec.field.as_deref(), spec.group_by.as_deref())
+}
+
+/// Groups rows by `group_by` (or a single group when `None`) and applies
+/// `function` to `field` within each group. Rows missing the field are skipped.
+pub fn aggregate_rows(
+    rows: &[(String, String)],
+    function: AggregateFunction,
+    field: Option<&str>,
+    group_by: Option<&str>,
+) -> Result<Vec<AggregateRow>> {
+    if field.is_none() && function != AggregateFunction::Count {
+        return Err(anyhow!("Aggregate function {:?} requires a field", function));
+    }
+
+    let mut groups: BTreeMap<String, (JsonValue, Vec<JsonValue>)> = BTreeMap::new();
+    for (key, value) in rows {
+        let group = match group_by {
+            Some(group_by) => match field_value(key, value, group_by) {
+                Some(group) => group,
+                None => continue,
+            },
+            None => JsonValue::Null,
+        };
+        let item = match field {
+            Some(field) => match field_value(key, value, field) {
+                Some(item) => item,
+                None => continue,
+            },
+            None => JsonValue::Null,
+        };
+        let sort_key = group_key(&group).unwrap_or_default();
+        groups.entry(sort_key).or_insert_with(|| (group, Vec::new())).1.push(item);
+    }
+
+    let mut result = Vec::new();
+    for (_, (group, items)) in groups {
+        let value = match function {
+            AggregateFunction::Count => JsonValue::from(items.len()),
+            AggregateFunction::Sum => JsonValue::from(numbers(&items).sum::<f64>()),
+            AggregateFunction::Avg => {
+                let values: Vec<f64> = numbers(&items).collect();
+                if values.is_empty() {
+                    JsonValue::Null
+                } else {
+                    JsonValue::from(values.iter().sum::<f64>() / values.len() as f64)
+                }
+            }
+            AggregateFunction::Min => extreme(&items, std::cmp::Ordering::Less),
+            AggregateFunction::Max => extreme(&items, std::cmp::Ordering::Greater),
+            AggregateFunction::Distinct => {
+                let mut distinct: BTreeMap<String, JsonValue> = BTreeMap::new();
+                for item in items {
+                    distinct.entry(item.to_string()).or_insert(item);
+                }
+                JsonValue::Array(distinct.into_values().collect())
+            }
+        };
+        result.push(AggregateRow { group, value });
+    }
+    Ok(result)
+}
+
+/// Resolves a field reference against a row: `_key`, `_value`, or a
+/// dot-separated path into the value parsed as JSON.
+pub fn field_value(key: &str, value: &str, field: &str) -> Option<JsonValue> {
+    if field == KEY_FIELD {
+        return Some(JsonValue::String(key.to_string()));
+    }
+    let parsed = serde_json::from_str::<JsonValue>(value)
+        .unwrap_or_else(|_| JsonValue::String(value.to_string()));
+    if field == VALUE_FIELD {
+        return Some(parsed);
+    }
+    json_path(&parsed, field)
+}
+
+fn group_key(value: &JsonValue) -> Option<String> {
+    match value {
+        JsonValue::Null => None,
+        JsonValue::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+fn numbers(items: &[JsonValue]) -> impl Iterator<Item = f64> + '_ {
+    items.iter().filter_map(|item| match item {
+        JsonValue::Number(n) => n.as_f64(),
+        JsonValue::String(s) => s.parse().ok(),
+        _ => None,
+    })
+}
+
+/// Numbers compare numerically, everything else by its string form; numbers
+/// sort before strings.
+fn extreme(items: &[JsonValue], wanted: std::cmp::Ordering) -> JsonValue {
+    items.iter()
+        .cloned()
+        .reduce(|best, item| if compare(&item, &best) == wanted { item } else { best })
+        .unwrap_or(JsonValue::Null)
+}
+
+fn compare(a: &JsonValue, b: &JsonValue) -> std::cmp::Ordering {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(x), Some(y)) => x.total_cmp(&y),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => group_key(a).cmp(&group_key(b)),
+    }
+}
+
+fn decode_rows(entries: Vec<(Vec<u8>, Vec<u8>)>) -> Vec<(String, String)> {
+    entries.into_iter()
+        .map(|(k, v)| (String::from_utf8_lossy(&k).into_owned(), String::from_utf8_lossy(&v).into_owned()))
+        .collect()
+}
+
+fn split_limit(args: &[String]) -> Result<(&[String], Option<usize>)> {
+    match args {
+        [rest @ .., keyword, n] if keyword.eq_ignore_ascii_case("limit") => {
+            let limit = n.parse().map_err(|_| anyhow!("Invalid limit '{}'", n))?;
+            Ok((rest, Some(limit)))
+        }
+        _ => Ok((args, None)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(entries: &[(&str, &str)]) -> Vec<(String, String)> {
+        entries.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_join_rows_on_field() {
+        let posts = rows(&[
+            ("p1", r#"{"author": "u1", "title": "Hello"}"#),
+            ("p2", r#"{"author": "u2", "title": "World"}"#),
+            ("p3", r#"{"author": "u9", "title": "Orphan"}"#),
+        ]);
+        let users = rows(&[("u1", "Alice"), ("u2", "Bob")]);
+
+        let joined = join_rows(&posts, &users, "author", KEY_FIELD, usize::MAX);
+        assert_eq!(joined.len(), 2);
+        assert_eq!(joined[0].left_key, "p1");
+        assert_eq!(joined[0].right_value, "Alice");
+        assert_eq!(joined[1].right_key, "u2");
+
+        // Limit stops the join early
+        assert_eq!(join_rows(&posts, &users, "author", KEY_FIELD, 1).len(), 1);
+    }
+
+    #[test]
+    fn test_aggregate_rows() {
+        let orders = rows(&[
+            ("o1", r#"{"region": "eu", "amount": 10}"#),
+            ("o2", r#"{"region": "eu", "amount": 30}"#),
+            ("o3", r#"{"region": "us", "amount": 5}"#),
+            ("o4", r#"{"region": "us"}"#),
+        ]);
+
+        let count = aggregate_rows(&orders, AggregateFunction::Count, None, None).unwrap();
+        assert_eq!(count[0].value, JsonValue::from(4));
+
+        let sums = aggregate_rows(&orders, AggregateFunction::Sum, Some("amount"), Some("region")).unwrap();
+        assert_eq!(sums[0].group, JsonValue::from("eu"));
+        assert_eq!(sums[0].value, JsonValue::from(40.0));
+        assert_eq!(sums[1].value, JsonValue::from(5.0));
+
+        let avg = aggregate_rows(&orders, AggregateFunction::Avg, Some("amount"), None).unwrap();
+        assert_eq!(avg[0].value, JsonValue::from(15.0));
+
+        let max = aggregate_rows(&orders, AggregateFunction::Max, Some("amount"), None).unwrap();
+        assert_eq!(max[0].value, JsonValue::from(30));
+
+        let distinct = aggregate_rows(&orders, AggregateFunction::Distinct, Some("region"), None).unwrap();
+        assert_eq!(distinct[0].value, serde_json::json!(["eu", "us"]));
+
+        // Every function other than count needs a field
+        assert!(aggregate_rows(&orders, AggregateFunction::Sum, None, None).is_err());
+    }
+
+    #[test]
+    fn test_parse_specs() {
+        let args: Vec<String> = ["posts", "users", "author", "_key", "limit", "5"].iter().map(|s| s.to_string()).collect();
+        let spec = JoinSpec::from_args(&args).unwrap();
+        assert_eq!(spec, JoinSpec::new("posts", "users", "author", "_key", Some(5)));
+
+        let args: Vec<String> = ["orders", "sum", "amount", "by", "region"].iter().map(|s| s.to_string()).collect();
+        let spec = AggregateSpec::from_args(&args).unwrap();
+        assert_eq!(spec.function, AggregateFunction::Sum);
+        assert_eq!(spec.field.as_deref(), Some("amount"));
+        assert_eq!(spec.group_by.as_deref(), Some("region"));
+    }
+}
@@ -53,4 +53,44 @@ impl QueryParser {
 
         Ok((query_type, args))
     }
-}
\ No newline at end of file
+
+    /// Recognizes the command form of the relational operators, e.g.
+    /// `join posts users author`. Only a command name followed by bare
+    /// words qualifies, so Lua calls such as `join ("a", "b")` or
+    /// `aggregate {namespace = "orders"}` are left to the Lua evaluator.
+    pub fn parse_command(query: &str) -> Option<(QueryType, Vec<String>)> {
+        match Self::parse(query) {
+            Ok((query_type @ (QueryType::Join | QueryType::Aggregate), args))
+                if args.iter().all(|arg| is_bare_word(arg)) =>
+            {
+                Some((query_type, args))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn is_bare_word(arg: &str) -> bool {
+    arg.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        let (query_type, args) = QueryParser::parse_command("join posts users author limit 5").unwrap();
+        assert_eq!(query_type, QueryType::Join);
+        assert_eq!(args, vec!["posts", "users", "author", "limit", "5"]);
+
+        let (query_type, _) = QueryParser::parse_command("AGGREGATE orders sum amount by region").unwrap();
+        assert_eq!(query_type, QueryType::Aggregate);
+
+        assert!(QueryParser::parse_command("join (\"a\", \"b\")").is_none());
+        assert!(QueryParser::parse_command("join \"posts\"").is_none());
+        assert!(QueryParser::parse_command("aggregate {namespace = \"orders\", fn = \"count\"}").is_none());
+        assert!(QueryParser::parse_command("select posts").is_none());
+        assert!(QueryParser::parse_command("return join(\"a\", \"b\")").is_none());
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use crate::core::RocksDBWrapper;
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+
+const POSTING_PREFIX: &[u8] = b"\x00fts:p:";
+const DOCUMENT_PREFIX: &[u8] = b"\x00fts:d:";
+const STATS_KEY: &[u8] = b"\x00fts:stats";
+
+// Standard Okapi BM25 parameters.
+const BM25_K1: f32 = 1.2;
+const BM25_B: f32 = 0.75;
+
+#[derive(Serialize, Deserialize, Default)]
+struct DocumentEntry {
+    length: u32,
+    terms: HashMap<String, u32>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct IndexStats {
+    documents: u64,
+    total_length: u64,
+}
+
+/// Inverted index over the string values of a namespace, scored with BM25.
+pub struct FullTextIndex {
+    db: Arc<RocksDBWrapper>,
+    write_lock: Mutex<()>,
+}
+
+impl FullTextIndex {
+    pub fn new(db: Arc<RocksDBWrapper>) -> Self {
+        Self { db, write_lock: Mutex::new(()) }
+    }
+
+    pub fn index(&self, key: &str, text: &str) -> Result<()> {
+        let _guard = self.write_lock.lock().unwrap();
+        let mut stats = self.stats()?;
+        let mut tx = self.db.transaction();
+
+        if let Some(previous) = self.document(key)? {
+            for term in previous.terms.keys() {
+                tx.delete(posting_key(term, key)).context("Failed to delete posting")?;
+            }
+            stats.documents -= 1;
+            stats.total_length -= previous.length as u64;
+        }
+
+        let terms = term_frequencies(text);
+        let length: u32 = terms.values().sum();
+        if length > 0 {
+            for (term, frequency) in &terms {
+                tx.put(posting_key(term, key), frequency.to_be_bytes())
+                    .context("Failed to store posting")?;
+            }
+            let entry = DocumentEntry { length, terms };
+            tx.put(document_key(key), serde_json::to_vec(&entry)?)
+                .context("Failed to store document entry")?;
+            stats.documents += 1;
+            stats.total_length += length as u64;
+        } else {
+            tx.delete(document_key(key)).context("Failed to delete document entry")?;
+        }
+
+        tx.put(STATS_KEY, serde_json::to_vec(&stats)?)
+            .context("Failed to store index stats")?;
+        tx.commit().context("Failed to commit full-text index update")?;
+        Ok(())
+    }
+
+    pub fn remove(&self, key: &str) -> Result<()> {
+        let _guard = self.write_lock.lock().unwrap();
+        let previous = match self.document(key)? {
+            Some(previous) => previous,
+            None => return Ok(()),
+        };
+        let mut stats = self.stats()?;
+        let mut tx = self.db.transaction();
+        for term in previous.terms.keys() {
+            tx.delete(posting_key(term, key)).context("Failed to delete posting")?;
+        }
+        tx.delete(document_key(key)).context("Failed to delete document entry")?;
+        stats.documents -= 1;
+        stats.total_length -= previous.length as u64;
+        tx.put(STATS_KEY, serde_json::to_vec(&stats)?)
+            .context("Failed to store index stats")?;
+        tx.commit().context("Failed to commit full-text index removal")?;
+        Ok(())
+    }
+
+    /// Returns up to `limit` keys ranked by BM25 score, best first.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<(String, f32)>> {
+        let stats = self.stats()?;
+        if stats.documents == 0 {
+            return Ok(Vec::new());
+        }
+        let document_count = stats.documents as f32;
+        let average_length = stats.total_length as f32 / document_count;
+
+        let mut query_terms = tokenize(query);
+        query_terms.sort();
+        query_terms.dedup();
+
+        let mut lengths: HashMap<String, f32> = HashMap::new();
+        let mut scores: HashMap<String, f32> = HashMap::new();
+        for term in query_terms {
+            let postings = self.db.scan_prefix(&posting_prefix(&term))?;
+            if postings.is_empty() {
+                continue;
+            }
+            let df = postings.len() as f32;
+            let idf = (1.0 + (document_count - df + 0.5) / (df + 0.5)).ln();
+            let prefix_len = posting_prefix(&term).len();
+
+            for (posting, frequency) in postings {
+                let key = String::from_utf8_lossy(&posting[prefix_len..]).into_owned();
+                let tf = decode_frequency(&frequency)? as f32;
+                let length = match lengths.get(&key) {
+                    Some(length) => *length,
+                    None => {
+                        let length = self.document(&key)?.map(|d| d.length as f32).unwrap_or(average_length);
+                        lengths.insert(key.clone(), length);
+                        length
+                    }
+                };
+                let norm = BM25_K1 * (1.0 - BM25_B + BM25_B * length / average_length);
+                *scores.entry(key).or_insert(0.0) += idf * tf * (BM25_K1 + 1.0) / (tf + norm);
+            }
+        }
+
+        let mut ranked: Vec<(String, f32)> = scores.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        ranked.truncate(limit);
+        Ok(ranked)
+    }
+
+    fn document(&self, key: &str) -> Result<Option<DocumentEntry>> {
+        match self.db.get(&document_key(key))? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)
+                .context("Corrupted full-text document entry")?)),
+            None => Ok(None),
+        }
+    }
+
+    fn stats(&self) -> Result<IndexStats> {
+        match self.db.get(STATS_KEY)? {
+            Some(bytes) => serde_json::from_slice(&bytes).context("Corrupted full-text index stats"),
+            None => Ok(IndexStats::default()),
+        }
+    }
+}
+
+/// Lowercases `text` and splits it into alphanumeric terms.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(|term| term.to_lowercase())
+        .collect()
+}
+
+fn term_frequencies(text: &str) -> HashMap<String, u32> {
+    let mut frequencies = HashMap::new();
+    for term in tokenize(text) {
+        *frequencies.entry(term).or_insert(0) += 1;
+    }
+    frequencies
+}
+
+fn posting_prefix(term: &str) -> Vec<u8> {
+    [POSTING_PREFIX, term.as_bytes(), b"\x00"].concat()
+}
+
+fn posting_key(term: &str, key: &str) -> Vec<u8> {
+    [posting_prefix(term).as_slice(), key.as_bytes()].concat()
+}
+
+fn document_key(key: &str) -> Vec<u8> {
+    [DOCUMENT_PREFIX, key.as_bytes()].concat()
+}
+
+fn decode_frequency(bytes: &[u8]) -> Result<u32> {
+    let bytes: [u8; 4] = bytes.try_into()
+        .map_err(|_| anyhow::anyhow!("Corrupted full-text posting"))?;
+    Ok(u32::from_be_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::temp_db;
+
+    #[test]
+    fn test_bm25_scoring() {
+        let index = FullTextIndex::new(temp_db());
+        index.index("a", "Rust vector database").unwrap();
+        index.index("b", "rust, rust and more rust").unwrap();
+        index.index("c", "Python web framework").unwrap();
+
+        let hits = index.search("RUST", 10).unwrap();
+        let keys: Vec<&str> = hits.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, vec!["b", "a"]);
+
+        // 3 documents of 3, 5 and 3 terms; "rust" occurs in 2 of them
+        let idf = (1.0f32 + (3.0 - 2.0 + 0.5) / (2.0 + 0.5)).ln();
+        let average_length = 11.0 / 3.0;
+        let norm = BM25_K1 * (1.0 - BM25_B + BM25_B * 3.0 / average_length);
+        let expected = idf * (BM25_K1 + 1.0) / (1.0 + norm);
+        assert!((hits[1].1 - expected).abs() < 1e-5);
+
+        // Query terms add up; repeated query terms count once
+        let combined = index.search("rust database database", 10).unwrap();
+        assert_eq!(combined[0].0, "a");
+        assert!(index.search("nothing", 10).unwrap().is_empty());
+        assert_eq!(index.search("rust", 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_reindex_and_remove() {
+        let index = FullTextIndex::new(temp_db());
+        index.index("a", "rust vector database").unwrap();
+        index.index("b", "rust").unwrap();
+
+        // Re-indexing drops the postings of the old text
+        index.index("a", "python").unwrap();
+        let keys: Vec<String> = index.search("rust database", 10).unwrap().into_iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, vec!["b"]);
+        assert_eq!(index.stats().unwrap().total_length, 2);
+
+        index.remove("b").unwrap();
+        index.remove("missing").unwrap();
+        assert!(index.search("rust", 10).unwrap().is_empty());
+        assert_eq!(index.search("python", 10).unwrap()[0].0, "a");
+        assert_eq!(index.stats().unwrap().documents, 1);
+
+        // Text without terms leaves no document behind
+        index.index("a", " --- ").unwrap();
+        assert_eq!(index.stats().unwrap().documents, 0);
+    }
+}
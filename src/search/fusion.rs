@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use anyhow::Result;
+
+/// Rank offset used by reciprocal rank fusion; 60 is the value from the
+/// original RRF paper and works well without tuning.
+const RRF_K: f32 = 60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FusionMethod {
+    ReciprocalRank,
+    Weighted,
+}
+
+impl FusionMethod {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "rrf" => Ok(FusionMethod::ReciprocalRank),
+            "weighted" => Ok(FusionMethod::Weighted),
+            _ => Err(anyhow::anyhow!("Invalid fusion method '{}'", name)),
+        }
+    }
+}
+
+/// Fuses two ranked lists (higher score is better) into one, weighting the
+/// vector list by `alpha` and the text list by `1 - alpha`.
+pub fn fuse(
+    method: FusionMethod,
+    alpha: f32,
+    vector_hits: &[(String, f32)],
+    text_hits: &[(String, f32)],
+) -> Vec<(String, f32)> {
+    let mut fused: HashMap<String, f32> = HashMap::new();
+    match method {
+        FusionMethod::ReciprocalRank => {
+            for (rank, (key, _)) in vector_hits.iter().enumerate() {
+                *fused.entry(key.clone()).or_insert(0.0) += alpha / (RRF_K + rank as f32 + 1.0);
+            }
+            for (rank, (key, _)) in text_hits.iter().enumerate() {
+                *fused.entry(key.clone()).or_insert(0.0) += (1.0 - alpha) / (RRF_K + rank as f32 + 1.0);
+            }
+        }
+        FusionMethod::Weighted => {
+            for (key, score) in normalize(vector_hits) {
+                *fused.entry(key).or_insert(0.0) += alpha * score;
+            }
+            for (key, score) in normalize(text_hits) {
+                *fused.entry(key).or_insert(0.0) += (1.0 - alpha) * score;
+            }
+        }
+    }
+
+    let mut ranked: Vec<(String, f32)> = fused.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked
+}
+
+/// Min-max normalizes scores into `[0, 1]`. A list where every score is
+/// equal maps to all ones.
+fn normalize(hits: &[(String, f32)]) -> Vec<(String, f32)> {
+    let min = hits.iter().map(|(_, s)| *s).fold(f32::INFINITY, f32::min);
+    let max = hits.iter().map(|(_, s)| *s).fold(f32::NEG_INFINITY, f32::max);
+    let range = max - min;
+    hits.iter()
+        .map(|(key, score)| {
+            let normalized = if range > 0.0 { (score - min) / range } else { 1.0 };
+            (key.clone(), normalized)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hits(entries: &[(&str, f32)]) -> Vec<(String, f32)> {
+        entries.iter().map(|(k, s)| (k.to_string(), *s)).collect()
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion() {
+        let vector = hits(&[("a", 0.9), ("b", 0.8), ("c", 0.1)]);
+        let text = hits(&[("b", 12.0), ("d", 3.0)]);
+
+        let fused = fuse(FusionMethod::ReciprocalRank, 0.5, &vector, &text);
+        let keys: Vec<&str> = fused.iter().map(|(k, _)| k.as_str()).collect();
+
+        // "b" appears in both lists and must win
+        assert_eq!(keys[0], "b");
+        assert_eq!(fused.len(), 4);
+    }
+
+    #[test]
+    fn test_weighted_fusion() {
+        let vector = hits(&[("a", -0.1), ("b", -0.5)]);
+        let text = hits(&[("b", 10.0), ("a", 2.0)]);
+
+        // Vector-only weighting follows the vector ranking
+        let fused = fuse(FusionMethod::Weighted, 1.0, &vector, &text);
+        assert_eq!(fused[0].0, "a");
+
+        // Text-only weighting follows the text ranking
+        let fused = fuse(FusionMethod::Weighted, 0.0, &vector, &text);
+        assert_eq!(fused[0].0, "b");
+    }
+
+    #[test]
+    fn test_parse_fusion_method() {
+        assert_eq!(FusionMethod::parse("rrf").unwrap(), FusionMethod::ReciprocalRank);
+        assert_eq!(FusionMethod::parse("weighted").unwrap(), FusionMethod::Weighted);
+        assert!(FusionMethod::parse("max").is_err());
+    }
+}
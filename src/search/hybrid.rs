@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use crate::core::{Namespace, VectorSearchOptions};
+use crate::search::fusion::{fuse, FusionMethod};
+use anyhow::Result;
+
+/// How many candidates each retriever contributes per requested result.
+const CANDIDATE_MULTIPLIER: usize = 4;
+
+pub struct HybridOptions {
+    pub k: usize,
+    pub alpha: f32,
+    pub fusion: FusionMethod,
+    /// Vector field to retrieve from; the default field when `None`.
+    pub field: Option<String>,
+}
+
+impl Default for HybridOptions {
+    fn default() -> Self {
+        Self {
+            k: 10,
+            alpha: 0.5,
+            fusion: FusionMethod::ReciprocalRank,
+            field: None,
+        }
+    }
+}
+
+pub struct HybridHit {
+    pub key: String,
+    pub value: Option<String>,
+    pub score: f32,
+    pub vector_distance: Option<f32>,
+    pub text_score: Option<f32>,
+}
+
+/// Runs vector and full-text retrieval over `namespace` and fuses the two
+/// rankings. `vector` is the embedding of `text`.
+pub fn hybrid_search(namespace: &Namespace, text: &str, vector: &[f32], options: &HybridOptions) -> Result<Vec<HybridHit>> {
+    let candidates = options.k * CANDIDATE_MULTIPLIER;
+
+    let mut distances: HashMap<String, f32> = HashMap::new();
+    let mut vector_hits = Vec::new();
+    let search_options = VectorSearchOptions { field: options.field.clone(), ..Default::default() };
+    for hit in namespace.search_vectors(vector, candidates, &search_options)? {
+        if let Some(key) = hit.key {
+            distances.insert(key.clone(), hit.distance);
+            // Smaller distances are better; fusion expects higher-is-better
+            vector_hits.push((key, -hit.distance));
+        }
+    }
+
+    let text_hits = namespace.fulltext.search(text, candidates)?;
+    let text_scores: HashMap<String, f32> = text_hits.iter().cloned().collect();
+
+    let mut hits = Vec::new();
+    for (key, score) in fuse(options.fusion, options.alpha, &vector_hits, &text_hits).into_iter().take(options.k) {
+        let value = namespace.db.get(key.as_bytes())?
+            .map(|v| String::from_utf8_lossy(&v).into_owned());
+        hits.push(HybridHit {
+            vector_distance: distances.get(&key).copied(),
+            text_score: text_scores.get(&key).copied(),
+            key,
+            value,
+            score,
+        });
+    }
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::temp_namespace;
+
+    #[test]
+    fn test_hybrid_search() {
+        let namespace = temp_namespace(2);
+        namespace.put("rust", "rust vector database").unwrap();
+        namespace.put("python", "python web framework").unwrap();
+        namespace.put("go", "go services").unwrap();
+        namespace.add_vector("rust", None, &[1.0, 0.0]).unwrap();
+        namespace.add_vector("python", None, &[0.0, 1.0]).unwrap();
+
+        let options = HybridOptions { k: 3, ..Default::default() };
+        let hits = hybrid_search(&namespace, "rust", &[0.9, 0.1], &options).unwrap();
+        assert_eq!(hits[0].key, "rust");
+        assert_eq!(hits[0].value.as_deref(), Some("rust vector database"));
+        assert!(hits[0].vector_distance.is_some() && hits[0].text_score.is_some());
+
+        // "python" is only found by the vector half, "go" by neither
+        let python = hits.iter().find(|hit| hit.key == "python").unwrap();
+        assert!(python.vector_distance.is_some() && python.text_score.is_none());
+        assert!(hits.iter().all(|hit| hit.key != "go"));
+    }
+}
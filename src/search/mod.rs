@@ -0,0 +1,7 @@
+mod fulltext;
+mod fusion;
+mod hybrid;
+
+pub use fulltext::{FullTextIndex, tokenize};
+pub use fusion::{fuse, FusionMethod};
+pub use hybrid::{hybrid_search, HybridHit, HybridOptions};
@@ -3,10 +3,12 @@ use axum::{
     Router,
     Json,
     extract::State,
+    response::sse::{Event, KeepAlive, Sse},
 };
+use futures::channel::mpsc::{self, UnboundedReceiver};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use crate::query::QueryExecutor;
+use crate::query::{QueryExecutor, TokenSink};
 
 #[derive(Deserialize)]
 struct QueryRequest {
@@ -34,6 +36,30 @@ async fn execute_query(
     Json(QueryResponse { result })
 }
 
+/// Runs a query and streams it as server-sent events: a `token` event with
+/// each piece of text generated by `llm_query`, then a `result` or `error`
+/// event once the query finishes. Every payload is a JSON string, since raw
+/// event data can't hold carriage returns.
+async fn execute_query_stream(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<QueryRequest>,
+) -> Sse<UnboundedReceiver<Result<Event, axum::Error>>> {
+    let (events, receiver) = mpsc::unbounded();
+    tokio::spawn(async move {
+        let tokens = events.clone();
+        // A failed send means the client disconnected, which stops generation
+        let sink: TokenSink = Arc::new(move |delta: &str| {
+            tokens.unbounded_send(Event::default().event("token").json_data(delta)).is_ok()
+        });
+        let event = match state.query_executor.execute_streaming(&payload.query, &payload.user_id, sink).await {
+            Ok(result) => Event::default().event("result").json_data(result),
+            Err(e) => Event::default().event("error").json_data(e.to_string()),
+        };
+        let _ = events.unbounded_send(event);
+    });
+    Sse::new(receiver).keep_alive(KeepAlive::default())
+}
+
 pub async fn run_server(port: u16, query_executor: QueryExecutor) -> anyhow::Result<()> {
     let app_state = Arc::new(AppState {
         query_executor: Arc::new(query_executor),
@@ -41,14 +67,19 @@ pub async fn run_server(port: u16, query_executor: QueryExecutor) -> anyhow::Res
 
     let app = Router::new()
         .route("/query", post(execute_query))
-        .with_state(app_state);
+        .route("/query/stream", post(execute_query_stream))
+        .with_state(app_state.clone());
 
     let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port));
     println!("AI-First DB Server listening on {}", addr);
 
     axum::Server::bind(&addr)
         .serve(app.into_make_service())
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
         .await?;
 
-    Ok(())
+    println!("Shutting down, saving vector indexes");
+    app_state.query_executor.persist()
 }
\ No newline at end of file
@@ -0,0 +1,112 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use usearch::MetricKind;
+
+/// One embedded piece of a longer document. `start` and `end` are byte
+/// offsets into the document value, `end` exclusive.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Chunk {
+    pub start: usize,
+    pub end: usize,
+    pub vector: Vec<f32>,
+}
+
+/// A chunk of a search hit together with its distance to the query.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChunkMatch {
+    pub chunk: usize,
+    pub start: usize,
+    pub end: usize,
+    pub distance: f32,
+}
+
+/// How the distances of a key's matching chunks combine into its rank.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ChunkAggregation {
+    /// Rank each key by its single closest chunk.
+    #[default]
+    MaxSim,
+    /// Rank each key by the summed similarity of all its matching chunks,
+    /// favouring documents that match in several places.
+    Sum,
+}
+
+impl ChunkAggregation {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "max" | "maxsim" => Ok(ChunkAggregation::MaxSim),
+            "sum" => Ok(ChunkAggregation::Sum),
+            _ => Err(anyhow!("Invalid chunk aggregation '{}': use 'max' or 'sum'", name)),
+        }
+    }
+
+    /// Scores a key from its matching chunks, sorted closest first. Higher
+    /// scores rank first.
+    pub fn score(&self, matches: &[ChunkMatch]) -> f32 {
+        match self {
+            ChunkAggregation::MaxSim => matches.first().map_or(f32::NEG_INFINITY, |m| -m.distance),
+            // 1 / (1 + d) keeps every chunk's contribution positive whatever
+            // the metric's distance range
+            ChunkAggregation::Sum => matches.iter().map(|m| 1.0 / (1.0 + m.distance.max(0.0))).sum(),
+        }
+    }
+}
+
+/// Metrics chunks can be rescored with outside the index.
+pub fn supports_chunks(metric: MetricKind) -> bool {
+    matches!(metric, MetricKind::Cos | MetricKind::IP | MetricKind::L2sq)
+}
+
+/// Distance between `a` and `b` as usearch reports it for `metric`.
+pub fn distance(metric: MetricKind, a: &[f32], b: &[f32]) -> Result<f32> {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    match metric {
+        MetricKind::IP => Ok(1.0 - dot),
+        MetricKind::Cos => {
+            let norms = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|y| y * y).sum::<f32>().sqrt();
+            Ok(if norms == 0.0 { 1.0 } else { 1.0 - dot / norms })
+        }
+        MetricKind::L2sq => Ok(a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()),
+        _ => Err(anyhow!("Chunked vectors cannot be rescored with this metric")),
+    }
+}
+
+/// Rescores every chunk against `query` and keeps the `count` closest.
+pub fn closest_chunks(metric: MetricKind, query: &[f32], chunks: &[Chunk], count: usize) -> Result<Vec<ChunkMatch>> {
+    let mut matches = Vec::with_capacity(chunks.len());
+    for (i, chunk) in chunks.iter().enumerate() {
+        matches.push(ChunkMatch {
+            chunk: i,
+            start: chunk.start,
+            end: chunk.end,
+            distance: distance(metric, query, &chunk.vector)?,
+        });
+    }
+    matches.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+    matches.truncate(count.max(1));
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_aggregation() {
+        let chunks = vec![
+            Chunk { start: 0, end: 10, vector: vec![1.0, 0.0] },
+            Chunk { start: 10, end: 20, vector: vec![0.0, 1.0] },
+            Chunk { start: 20, end: 30, vector: vec![0.9, 0.1] },
+        ];
+        let matches = closest_chunks(MetricKind::L2sq, &[1.0, 0.0], &chunks, 2).unwrap();
+        assert_eq!(matches.iter().map(|m| m.chunk).collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!((matches[1].start, matches[1].end), (20, 30));
+
+        assert_eq!(ChunkAggregation::MaxSim.score(&matches), 0.0);
+        // Two close chunks outscore a single exact one under sum
+        assert!(ChunkAggregation::Sum.score(&matches) > ChunkAggregation::Sum.score(&matches[..1]));
+
+        assert_eq!(ChunkAggregation::parse("sum").unwrap(), ChunkAggregation::Sum);
+        assert!(ChunkAggregation::parse("avg").is_err());
+    }
+}
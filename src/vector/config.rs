@@ -0,0 +1,249 @@
+use std::borrow::Cow;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use usearch::{MetricKind, ScalarKind};
+use crate::vector::{VectorTransform, apply_transforms, supports_chunks, transformed_dimensions};
+
+/// Name of the vector field every namespace is created with.
+pub const DEFAULT_VECTOR_FIELD: &str = "default";
+
+/// Field names end up in index file names, so keep them to a safe alphabet.
+pub fn validate_field_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.len() > 64 || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(anyhow!("Invalid vector field name '{}': use 1-64 letters, digits or '_'", name));
+    }
+    Ok(())
+}
+
+/// Parameters a namespace's vector index was created with. Persisted in the
+/// namespace so the index can be recreated identically on startup.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct VectorConfig {
+    pub dimensions: usize,
+    #[serde(with = "metric_serde")]
+    pub metric: MetricKind,
+    #[serde(with = "scalar_serde")]
+    pub quantization: ScalarKind,
+    /// Lets one key own many vectors, e.g. the chunks of a long document.
+    #[serde(default)]
+    pub multi: bool,
+    /// HNSW graph degree; 0 lets usearch choose.
+    #[serde(default)]
+    pub connectivity: usize,
+    /// Candidate list size while inserting; 0 lets usearch choose.
+    #[serde(default)]
+    pub expansion_add: usize,
+    /// Default candidate list size while searching; 0 lets usearch choose.
+    #[serde(default)]
+    pub expansion_search: usize,
+    /// Applied, in order, to vectors before they are indexed or searched;
+    /// `dimensions` is what they produce.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transforms: Vec<VectorTransform>,
+}
+
+impl VectorConfig {
+    pub fn new(dimensions: usize, metric: MetricKind, quantization: ScalarKind) -> Self {
+        Self {
+            dimensions,
+            metric,
+            quantization,
+            multi: false,
+            connectivity: 0,
+            expansion_add: 0,
+            expansion_search: 0,
+            transforms: Vec::new(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.dimensions == 0 {
+            return Err(anyhow!("Vector dimensions must be positive"));
+        }
+        if self.multi && !supports_chunks(self.metric) {
+            return Err(anyhow!("Multi-vector fields support the cosine, ip and euclidean metrics only"));
+        }
+        if is_binary_metric(self.metric) && self.quantization != ScalarKind::B1 {
+            return Err(anyhow!("The {} metric requires the b1 scalar kind", metric_name(self.metric)));
+        }
+        if self.metric == MetricKind::Haversine && self.dimensions != 2 {
+            return Err(anyhow!("The haversine metric requires 2 dimensions (latitude, longitude)"));
+        }
+        // Coordinates need the precision of floats; f16 and i8 would move
+        // points by kilometres or clamp them to [-1, 1]
+        if self.metric == MetricKind::Haversine && !matches!(self.quantization, ScalarKind::F32 | ScalarKind::F64) {
+            return Err(anyhow!("The haversine metric requires the f32 or f64 scalar kind"));
+        }
+        if let Some(produced) = transformed_dimensions(&self.transforms)?.filter(|d| *d != self.dimensions) {
+            return Err(anyhow!("Vector transforms produce {} dimensions, but the field has {}", produced, self.dimensions));
+        }
+        Ok(())
+    }
+
+    /// Runs `vector` through the transforms and checks the result, giving
+    /// the vector as the index stores and searches it.
+    pub fn prepare<'a>(&self, vector: &'a [f32]) -> Result<Cow<'a, [f32]>> {
+        let prepared = if self.transforms.is_empty() {
+            Cow::Borrowed(vector)
+        } else {
+            Cow::Owned(apply_transforms(&self.transforms, vector)?)
+        };
+        self.check_vector(&prepared)?;
+        Ok(prepared)
+    }
+
+    /// Checks that `vector` fits this configuration before it reaches the
+    /// index, which would otherwise read past short vectors or silently
+    /// clamp out-of-range values.
+    pub fn check_vector(&self, vector: &[f32]) -> Result<()> {
+        if vector.len() != self.dimensions {
+            return Err(anyhow!("Expected a vector of {} dimensions, got {}", self.dimensions, vector.len()));
+        }
+        if let Some(value) = vector.iter().find(|value| !value.is_finite()) {
+            return Err(anyhow!("Vector contains non-finite value {}", value));
+        }
+        match self.quantization {
+            ScalarKind::B1 if vector.iter().any(|value| *value != 0.0 && *value != 1.0) => {
+                Err(anyhow!("Binary vectors may only contain 0 and 1"))
+            }
+            ScalarKind::I8 if vector.iter().any(|value| !(-1.0..=1.0).contains(value)) => {
+                Err(anyhow!("i8 vectors must be normalized to [-1, 1]"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Metrics defined over bit vectors.
+fn is_binary_metric(metric: MetricKind) -> bool {
+    matches!(metric, MetricKind::Hamming | MetricKind::Tanimoto | MetricKind::Sorensen)
+}
+
+/// Expands a bit-packed hash into one 0/1 value per bit, most significant
+/// bit first, as binary fields expect.
+pub fn unpack_bits(bytes: &[u8], dimensions: usize) -> Result<Vec<f32>> {
+    if bytes.len() != dimensions.div_ceil(8) {
+        return Err(anyhow!("Expected {} bytes for {} bits, got {}", dimensions.div_ceil(8), dimensions, bytes.len()));
+    }
+    Ok((0..dimensions)
+        .map(|bit| if bytes[bit / 8] & (0x80 >> (bit % 8)) != 0 { 1.0 } else { 0.0 })
+        .collect())
+}
+
+pub fn parse_metric(name: &str) -> Result<MetricKind> {
+    match name {
+        "cosine" => Ok(MetricKind::Cos),
+        "euclidean" | "l2sq" => Ok(MetricKind::L2sq),
+        "ip" | "inner_product" => Ok(MetricKind::IP),
+        "pearson" => Ok(MetricKind::Pearson),
+        "haversine" => Ok(MetricKind::Haversine),
+        "divergence" => Ok(MetricKind::Divergence),
+        "hamming" => Ok(MetricKind::Hamming),
+        // On bit vectors the Tanimoto coefficient is the Jaccard index
+        "tanimoto" | "jaccard" => Ok(MetricKind::Tanimoto),
+        "sorensen" => Ok(MetricKind::Sorensen),
+        _ => Err(anyhow!("Invalid metric kind '{}'", name)),
+    }
+}
+
+pub fn metric_name(metric: MetricKind) -> &'static str {
+    match metric {
+        MetricKind::Cos => "cosine",
+        MetricKind::L2sq => "euclidean",
+        MetricKind::IP => "ip",
+        MetricKind::Pearson => "pearson",
+        MetricKind::Haversine => "haversine",
+        MetricKind::Divergence => "divergence",
+        MetricKind::Hamming => "hamming",
+        MetricKind::Tanimoto => "tanimoto",
+        MetricKind::Sorensen => "sorensen",
+        _ => "unknown",
+    }
+}
+
+pub fn parse_scalar(name: &str) -> Result<ScalarKind> {
+    match name {
+        "f64" => Ok(ScalarKind::F64),
+        "f32" => Ok(ScalarKind::F32),
+        "f16" => Ok(ScalarKind::F16),
+        "i8" => Ok(ScalarKind::I8),
+        "b1" => Ok(ScalarKind::B1),
+        _ => Err(anyhow!("Invalid scalar kind '{}'", name)),
+    }
+}
+
+pub fn scalar_name(scalar: ScalarKind) -> &'static str {
+    match scalar {
+        ScalarKind::F64 => "f64",
+        ScalarKind::F32 => "f32",
+        ScalarKind::F16 => "f16",
+        ScalarKind::I8 => "i8",
+        ScalarKind::B1 => "b1",
+        _ => "unknown",
+    }
+}
+
+mod metric_serde {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use usearch::MetricKind;
+
+    pub fn serialize<S: Serializer>(metric: &MetricKind, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(super::metric_name(*metric))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<MetricKind, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        super::parse_metric(&name).map_err(serde::de::Error::custom)
+    }
+}
+
+mod scalar_serde {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use usearch::ScalarKind;
+
+    pub fn serialize<S: Serializer>(scalar: &ScalarKind, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(super::scalar_name(*scalar))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ScalarKind, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        super::parse_scalar(&name).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vector_validation() {
+        for name in ["cosine", "euclidean", "ip", "pearson", "haversine", "divergence", "hamming", "tanimoto", "sorensen"] {
+            assert_eq!(metric_name(parse_metric(name).unwrap()), name);
+        }
+        assert_eq!(parse_metric("jaccard").unwrap(), MetricKind::Tanimoto);
+        for name in ["f64", "f32", "f16", "i8", "b1"] {
+            assert_eq!(scalar_name(parse_scalar(name).unwrap()), name);
+        }
+
+        let binary = VectorConfig::new(16, MetricKind::Hamming, ScalarKind::B1);
+        assert!(binary.validate().is_ok());
+        assert!(VectorConfig::new(16, MetricKind::Hamming, ScalarKind::F32).validate().is_err());
+        assert!(VectorConfig::new(3, MetricKind::Haversine, ScalarKind::F32).validate().is_err());
+        assert!(VectorConfig::new(2, MetricKind::Haversine, ScalarKind::F64).validate().is_ok());
+        for scalar in [ScalarKind::F16, ScalarKind::I8, ScalarKind::B1] {
+            assert!(VectorConfig::new(2, MetricKind::Haversine, scalar).validate().is_err());
+        }
+
+        let bits = unpack_bits(&[0b1000_0001, 0xff], 16).unwrap();
+        assert_eq!(&bits[..8], &[1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0]);
+        assert!(binary.check_vector(&bits).is_ok());
+        assert!(binary.check_vector(&[0.5; 16]).is_err());
+        assert!(binary.check_vector(&bits[..8]).is_err());
+        assert!(unpack_bits(&[0xff], 16).is_err());
+
+        let quantized = VectorConfig::new(2, MetricKind::Cos, ScalarKind::I8);
+        assert!(quantized.check_vector(&[0.5, -1.0]).is_ok());
+        assert!(quantized.check_vector(&[2.0, 0.0]).is_err());
+        assert!(quantized.check_vector(&[f32::NAN, 0.0]).is_err());
+    }
+}
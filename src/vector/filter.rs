@@ -0,0 +1,270 @@
+use std::cmp::Ordering;
+use anyhow::{Result, anyhow};
+use serde_json::Value as JsonValue;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Boolean filter over document fields, e.g.
+/// `lang = 'de' and (year >= 2020 or pinned = true)`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpr {
+    Compare { field: String, op: CompareOp, value: JsonValue },
+    In { field: String, values: Vec<JsonValue> },
+    And(Box<FilterExpr>, Box<FilterExpr>),
+    Or(Box<FilterExpr>, Box<FilterExpr>),
+    Not(Box<FilterExpr>),
+}
+
+impl FilterExpr {
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.parse_or()?;
+        if parser.pos != parser.tokens.len() {
+            return Err(anyhow!("Unexpected token {:?} in filter", parser.tokens[parser.pos]));
+        }
+        Ok(expr)
+    }
+
+    /// Evaluates the filter, resolving field names through `lookup`.
+    /// Comparisons against a missing field are false.
+    pub fn matches<F: Fn(&str) -> Option<JsonValue>>(&self, lookup: &F) -> bool {
+        match self {
+            FilterExpr::Compare { field, op, value } => match lookup(field) {
+                Some(actual) => compare(&actual, *op, value),
+                None => false,
+            },
+            FilterExpr::In { field, values } => match lookup(field) {
+                Some(actual) => values.iter().any(|value| compare(&actual, CompareOp::Eq, value)),
+                None => false,
+            },
+            FilterExpr::And(left, right) => left.matches(lookup) && right.matches(lookup),
+            FilterExpr::Or(left, right) => left.matches(lookup) || right.matches(lookup),
+            FilterExpr::Not(inner) => !inner.matches(lookup),
+        }
+    }
+}
+
+/// Resolves a dot-separated `path` inside `value`; `null` counts as missing.
+pub fn json_path(value: &JsonValue, path: &str) -> Option<JsonValue> {
+    let mut current = value;
+    for part in path.split('.') {
+        current = current.get(part)?;
+    }
+    match current {
+        JsonValue::Null => None,
+        other => Some(other.clone()),
+    }
+}
+
+fn compare(actual: &JsonValue, op: CompareOp, expected: &JsonValue) -> bool {
+    let ordering = match (actual, expected) {
+        (JsonValue::Number(a), JsonValue::Number(b)) => match (a.as_f64(), b.as_f64()) {
+            (Some(a), Some(b)) => a.partial_cmp(&b),
+            _ => None,
+        },
+        (JsonValue::String(a), JsonValue::String(b)) => Some(a.cmp(b)),
+        (JsonValue::Bool(a), JsonValue::Bool(b)) => Some(a.cmp(b)),
+        (JsonValue::Null, JsonValue::Null) => Some(Ordering::Equal),
+        _ => None,
+    };
+    match (op, ordering) {
+        (CompareOp::Eq, Some(ordering)) => ordering == Ordering::Equal,
+        (CompareOp::Ne, Some(ordering)) => ordering != Ordering::Equal,
+        // Values of different types are never equal
+        (CompareOp::Ne, None) => true,
+        (CompareOp::Lt, Some(ordering)) => ordering == Ordering::Less,
+        (CompareOp::Le, Some(ordering)) => ordering != Ordering::Greater,
+        (CompareOp::Gt, Some(ordering)) => ordering == Ordering::Greater,
+        (CompareOp::Ge, Some(ordering)) => ordering != Ordering::Less,
+        (_, None) => false,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Literal(JsonValue),
+    Op(CompareOp),
+    And,
+    Or,
+    Not,
+    In,
+    LParen,
+    RParen,
+    Comma,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' => { tokens.push(Token::LParen); i += 1; }
+            ')' => { tokens.push(Token::RParen); i += 1; }
+            ',' => { tokens.push(Token::Comma); i += 1; }
+            '=' => {
+                tokens.push(Token::Op(CompareOp::Eq));
+                i += if chars.get(i + 1) == Some(&'=') { 2 } else { 1 };
+            }
+            '!' if chars.get(i + 1) == Some(&'=') => { tokens.push(Token::Op(CompareOp::Ne)); i += 2; }
+            '<' if chars.get(i + 1) == Some(&'=') => { tokens.push(Token::Op(CompareOp::Le)); i += 2; }
+            '>' if chars.get(i + 1) == Some(&'=') => { tokens.push(Token::Op(CompareOp::Ge)); i += 2; }
+            '<' => { tokens.push(Token::Op(CompareOp::Lt)); i += 1; }
+            '>' => { tokens.push(Token::Op(CompareOp::Gt)); i += 1; }
+            '\'' | '"' => {
+                let end = chars[i + 1..].iter().position(|&ch| ch == c)
+                    .ok_or_else(|| anyhow!("Unterminated string in filter"))?;
+                let text: String = chars[i + 1..i + 1 + end].iter().collect();
+                tokens.push(Token::Literal(JsonValue::String(text)));
+                i += end + 2;
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_digit() || matches!(chars[i], '.' | 'e' | 'E')) {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let number: JsonValue = serde_json::from_str(&text)
+                    .map_err(|_| anyhow!("Invalid number '{}' in filter", text))?;
+                tokens.push(Token::Literal(number));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '.')) {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                tokens.push(match word.to_lowercase().as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    "in" => Token::In,
+                    "true" => Token::Literal(JsonValue::Bool(true)),
+                    "false" => Token::Literal(JsonValue::Bool(false)),
+                    "null" => Token::Literal(JsonValue::Null),
+                    _ => Token::Ident(word),
+                });
+            }
+            other => return Err(anyhow!("Unexpected character '{}' in filter", other)),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn parse_or(&mut self) -> Result<FilterExpr> {
+        let mut expr = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            expr = FilterExpr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<FilterExpr> {
+        let mut expr = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            expr = FilterExpr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<FilterExpr> {
+        match self.next() {
+            Some(Token::Not) => Ok(FilterExpr::Not(Box::new(self.parse_unary()?))),
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    _ => Err(anyhow!("Expected ')' in filter")),
+                }
+            }
+            Some(Token::Ident(field)) => match self.next() {
+                Some(Token::Op(op)) => match self.next() {
+                    Some(Token::Literal(value)) => Ok(FilterExpr::Compare { field, op, value }),
+                    _ => Err(anyhow!("Expected a value after '{}' comparison", field)),
+                },
+                Some(Token::In) => Ok(FilterExpr::In { field, values: self.parse_list()? }),
+                _ => Err(anyhow!("Expected a comparison after '{}'", field)),
+            },
+            other => Err(anyhow!("Unexpected token {:?} in filter", other)),
+        }
+    }
+
+    fn parse_list(&mut self) -> Result<Vec<JsonValue>> {
+        if self.next() != Some(Token::LParen) {
+            return Err(anyhow!("Expected '(' after 'in'"));
+        }
+        let mut values = Vec::new();
+        loop {
+            match self.next() {
+                Some(Token::Literal(value)) => values.push(value),
+                _ => return Err(anyhow!("Expected a value in 'in' list")),
+            }
+            match self.next() {
+                Some(Token::Comma) => continue,
+                Some(Token::RParen) => return Ok(values),
+                _ => return Err(anyhow!("Expected ',' or ')' in 'in' list")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_filter_expressions() {
+        let doc = json!({"lang": "de", "year": 2021, "pinned": false});
+        let lookup = |field: &str| doc.get(field).cloned();
+
+        let filter = FilterExpr::parse("lang = 'de' and year >= 2020").unwrap();
+        assert!(filter.matches(&lookup));
+
+        let filter = FilterExpr::parse("lang == \"en\" or (year < 2022 and not pinned = true)").unwrap();
+        assert!(filter.matches(&lookup));
+
+        let filter = FilterExpr::parse("lang in ('fr', 'es')").unwrap();
+        assert!(!filter.matches(&lookup));
+
+        // Missing fields never match
+        let filter = FilterExpr::parse("author = 'x'").unwrap();
+        assert!(!filter.matches(&lookup));
+
+        // Malformed filters are rejected
+        assert!(FilterExpr::parse("lang = ").is_err());
+        assert!(FilterExpr::parse("(year > 1").is_err());
+        assert!(FilterExpr::parse("year > 1 2").is_err());
+    }
+}
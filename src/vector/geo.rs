@@ -0,0 +1,98 @@
+use anyhow::{Result, anyhow};
+use serde_json::json;
+use crate::vector::filter::CompareOp;
+use crate::vector::FilterExpr;
+
+/// Mean Earth radius; usearch's Haversine metric returns central angles on
+/// the unit sphere, which this scales to kilometres.
+pub const EARTH_RADIUS_KM: f32 = 6371.0;
+
+/// Vector metadata fields the coordinates of a point are stored under, so
+/// bounding boxes can be evaluated as filters.
+pub const LATITUDE_FIELD: &str = "geo.lat";
+pub const LONGITUDE_FIELD: &str = "geo.lon";
+
+/// Builds the `[lat, lon]` vector Haversine indexes hold. usearch converts
+/// the coordinates to radians itself, so they stay in degrees.
+pub fn geo_vector(lat: f64, lon: f64) -> Result<Vec<f32>> {
+    if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+        return Err(anyhow!("Invalid coordinates ({}, {})", lat, lon));
+    }
+    Ok(vec![lat as f32, lon as f32])
+}
+
+pub fn to_km(distance: f32) -> f32 {
+    distance * EARTH_RADIUS_KM
+}
+
+pub fn from_km(km: f32) -> f32 {
+    km / EARTH_RADIUS_KM
+}
+
+/// A latitude/longitude box in degrees. A box whose `min_lon` is greater
+/// than its `max_lon` crosses the antimeridian.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min_lat: f64,
+    pub min_lon: f64,
+    pub max_lat: f64,
+    pub max_lon: f64,
+}
+
+impl BoundingBox {
+    pub fn new(min_lat: f64, min_lon: f64, max_lat: f64, max_lon: f64) -> Result<Self> {
+        geo_vector(min_lat, min_lon)?;
+        geo_vector(max_lat, max_lon)?;
+        if min_lat > max_lat {
+            return Err(anyhow!("Bounding box min_lat {} is above max_lat {}", min_lat, max_lat));
+        }
+        Ok(Self { min_lat, min_lon, max_lat, max_lon })
+    }
+
+    /// A filter over the stored coordinates that accepts points in the box.
+    pub fn filter(&self) -> FilterExpr {
+        let compare = |field: &str, op, value: f64| FilterExpr::Compare {
+            field: field.to_string(),
+            op,
+            value: json!(value),
+        };
+        let latitude = FilterExpr::And(
+            Box::new(compare(LATITUDE_FIELD, CompareOp::Ge, self.min_lat)),
+            Box::new(compare(LATITUDE_FIELD, CompareOp::Le, self.max_lat)),
+        );
+        let west = Box::new(compare(LONGITUDE_FIELD, CompareOp::Ge, self.min_lon));
+        let east = Box::new(compare(LONGITUDE_FIELD, CompareOp::Le, self.max_lon));
+        let longitude = if self.min_lon <= self.max_lon {
+            FilterExpr::And(west, east)
+        } else {
+            FilterExpr::Or(west, east)
+        };
+        FilterExpr::And(Box::new(latitude), Box::new(longitude))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::json_path;
+
+    #[test]
+    fn test_bounding_box() {
+        let point = |lat: f64, lon: f64| json!({"geo": {"lat": lat, "lon": lon}});
+        let matches = |bbox: &BoundingBox, doc: &serde_json::Value| bbox.filter().matches(&|field: &str| json_path(doc, field));
+
+        let europe = BoundingBox::new(35.0, -10.0, 70.0, 40.0).unwrap();
+        assert!(matches(&europe, &point(48.85, 2.35)));
+        assert!(!matches(&europe, &point(40.71, -74.0)));
+
+        // Boxes may wrap around the antimeridian
+        let pacific = BoundingBox::new(-30.0, 170.0, 30.0, -170.0).unwrap();
+        assert!(matches(&pacific, &point(0.0, 179.0)));
+        assert!(matches(&pacific, &point(0.0, -175.0)));
+        assert!(!matches(&pacific, &point(0.0, 0.0)));
+
+        assert!(BoundingBox::new(10.0, 0.0, -10.0, 5.0).is_err());
+        assert!(geo_vector(91.0, 0.0).is_err());
+        assert!((to_km(from_km(12.5)) - 12.5).abs() < 1e-4);
+    }
+}
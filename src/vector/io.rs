@@ -0,0 +1,240 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use anyhow::{Result, Context, anyhow};
+
+/// Resolves `path`, relative to `dir` unless absolute, and checks that the
+/// file it names lies inside `dir` once `..` and symlinks are followed.
+pub fn resolve_within(dir: &Path, path: &Path) -> Result<PathBuf> {
+    let root = dir.canonicalize().context(format!("Failed to resolve {}", dir.display()))?;
+    let resolved = root.join(path).canonicalize().context(format!("Failed to resolve {}", path.display()))?;
+    if !resolved.starts_with(&root) {
+        return Err(anyhow!("{} is outside {}", path.display(), dir.display()));
+    }
+    Ok(resolved)
+}
+
+/// Reads a vector set from `path`, picking the format from its extension:
+/// `.fvecs`, `.bvecs` or `.npy` (2-D, little-endian `f4` or `f8`).
+pub fn read_vectors(path: &Path) -> Result<Vec<Vec<f32>>> {
+    let bytes = fs::read(path).context(format!("Failed to read {}", path.display()))?;
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("fvecs") => parse_fvecs(&bytes),
+        Some("bvecs") => parse_bvecs(&bytes),
+        Some("npy") => parse_npy(&bytes),
+        _ => Err(anyhow!("Unsupported vector file {}: expected .fvecs, .bvecs or .npy", path.display())),
+    }
+}
+
+/// Reads the keys matching the rows of a vector file: a 1-D `.npy` array of
+/// integer ids, or any other file as text with one key per line.
+pub fn read_keys(path: &Path) -> Result<Vec<String>> {
+    let bytes = fs::read(path).context(format!("Failed to read {}", path.display()))?;
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("npy") => parse_npy_ids(&bytes),
+        _ => {
+            let text = String::from_utf8(bytes).context(format!("{} is not UTF-8 text", path.display()))?;
+            Ok(text.lines().map(str::to_string).collect())
+        }
+    }
+}
+
+/// Reads a vector file and its key file side by side, checking that they
+/// have the same number of rows. Without a key file each vector is keyed by
+/// its row number.
+pub fn read_dataset(vectors: &Path, keys: Option<&Path>) -> Result<(Vec<String>, Vec<Vec<f32>>)> {
+    let (vectors, keys) = thread::scope(|scope| {
+        let keys = keys.map(|path| scope.spawn(move || read_keys(path)));
+        let vectors = read_vectors(vectors);
+        let keys = keys
+            .map(|worker| worker.join().map_err(|_| anyhow!("Key reader panicked"))?)
+            .transpose();
+        (vectors, keys)
+    });
+    let vectors = vectors?;
+    let keys = match keys? {
+        Some(keys) if keys.len() != vectors.len() => {
+            return Err(anyhow!("Found {} keys for {} vectors", keys.len(), vectors.len()));
+        }
+        Some(keys) => keys,
+        None => (0..vectors.len()).map(|row| row.to_string()).collect(),
+    };
+    Ok((keys, vectors))
+}
+
+/// Parses the TEXMEX `.fvecs` layout: each vector is a little-endian `i32`
+/// dimension count followed by that many `f32` values.
+pub fn parse_fvecs(bytes: &[u8]) -> Result<Vec<Vec<f32>>> {
+    parse_vecs(bytes, 4, "fvecs", |c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+}
+
+/// Parses the TEXMEX `.bvecs` layout, where each value is a single `u8`.
+pub fn parse_bvecs(bytes: &[u8]) -> Result<Vec<Vec<f32>>> {
+    parse_vecs(bytes, 1, "bvecs", |c| c[0] as f32)
+}
+
+fn parse_vecs(bytes: &[u8], width: usize, format: &str, value: impl Fn(&[u8]) -> f32) -> Result<Vec<Vec<f32>>> {
+    let mut vectors = Vec::new();
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let dimensions = read_dimensions(rest)?;
+        let body = rest.get(4..4 + dimensions * width)
+            .ok_or_else(|| anyhow!("Truncated .{} vector {}", format, vectors.len()))?;
+        vectors.push(body.chunks_exact(width).map(&value).collect());
+        rest = &rest[4 + dimensions * width..];
+    }
+    Ok(vectors)
+}
+
+fn read_dimensions(bytes: &[u8]) -> Result<usize> {
+    let header: [u8; 4] = bytes.get(..4)
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| anyhow!("Truncated vector header"))?;
+    let dimensions = i32::from_le_bytes(header);
+    if dimensions <= 0 {
+        return Err(anyhow!("Invalid vector dimension count {}", dimensions));
+    }
+    Ok(dimensions as usize)
+}
+
+/// Parses a 2-D C-ordered NumPy array of little-endian `f4` or `f8` values.
+pub fn parse_npy(bytes: &[u8]) -> Result<Vec<Vec<f32>>> {
+    let (header, data) = split_npy(bytes)?;
+    let width = if header.contains("'<f4'") {
+        4
+    } else if header.contains("'<f8'") {
+        8
+    } else {
+        return Err(anyhow!("Unsupported .npy dtype; expected '<f4' or '<f8'"));
+    };
+    let (rows, dimensions) = match npy_shape(header)?.as_slice() {
+        [rows, dimensions] => (*rows, *dimensions),
+        dims => return Err(anyhow!("Expected a 2-D .npy array, got {} dimensions", dims.len())),
+    };
+
+    if data.len() != rows * dimensions * width {
+        return Err(anyhow!("Expected {} bytes of .npy data, found {}", rows * dimensions * width, data.len()));
+    }
+    let values: Vec<f32> = if width == 4 {
+        data.chunks_exact(4).map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]])).collect()
+    } else {
+        data.chunks_exact(8).map(|c| f64::from_le_bytes(c.try_into().unwrap()) as f32).collect()
+    };
+    Ok(values.chunks(dimensions.max(1)).map(|row| row.to_vec()).collect())
+}
+
+/// Parses a 1-D NumPy array of little-endian integers into decimal keys.
+pub fn parse_npy_ids(bytes: &[u8]) -> Result<Vec<String>> {
+    let (header, data) = split_npy(bytes)?;
+    let rows = match npy_shape(header)?.as_slice() {
+        [rows] | [rows, 1] => *rows,
+        _ => return Err(anyhow!("Expected a 1-D .npy array of ids")),
+    };
+    let (width, decode): (usize, fn(&[u8]) -> String) = if header.contains("'<i8'") {
+        (8, |c| i64::from_le_bytes(c.try_into().unwrap()).to_string())
+    } else if header.contains("'<u8'") {
+        (8, |c| u64::from_le_bytes(c.try_into().unwrap()).to_string())
+    } else if header.contains("'<i4'") {
+        (4, |c| i32::from_le_bytes(c.try_into().unwrap()).to_string())
+    } else if header.contains("'<u4'") {
+        (4, |c| u32::from_le_bytes(c.try_into().unwrap()).to_string())
+    } else {
+        return Err(anyhow!("Unsupported .npy id dtype; expected '<i8', '<u8', '<i4' or '<u4'"));
+    };
+    if data.len() != rows * width {
+        return Err(anyhow!("Expected {} bytes of .npy data, found {}", rows * width, data.len()));
+    }
+    Ok(data.chunks_exact(width).map(decode).collect())
+}
+
+/// Splits a `.npy` file into its header dictionary and C-ordered data.
+fn split_npy(bytes: &[u8]) -> Result<(&str, &[u8])> {
+    if bytes.len() < 10 || &bytes[..6] != b"\x93NUMPY" {
+        return Err(anyhow!("Not a .npy file"));
+    }
+    let (header_len, header_start) = match bytes[6] {
+        1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+        2 | 3 => {
+            let len = bytes.get(8..12).ok_or_else(|| anyhow!("Truncated .npy header"))?;
+            (u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize, 12)
+        }
+        version => return Err(anyhow!("Unsupported .npy version {}", version)),
+    };
+    let header = bytes.get(header_start..header_start + header_len)
+        .ok_or_else(|| anyhow!("Truncated .npy header"))?;
+    let header = std::str::from_utf8(header).context("Invalid .npy header")?;
+    if header.contains("'fortran_order': True") {
+        return Err(anyhow!("Fortran-ordered .npy arrays are not supported"));
+    }
+    Ok((header, &bytes[header_start + header_len..]))
+}
+
+fn npy_shape(header: &str) -> Result<Vec<usize>> {
+    let start = header.find("'shape':").ok_or_else(|| anyhow!("Missing .npy shape"))?;
+    let shape = &header[start..];
+    let open = shape.find('(').ok_or_else(|| anyhow!("Invalid .npy shape"))?;
+    let close = shape.find(')').ok_or_else(|| anyhow!("Invalid .npy shape"))?;
+    shape[open + 1..close]
+        .split(',')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map(|d| d.parse().map_err(|_| anyhow!("Invalid .npy shape")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vector_files() {
+        let mut fvecs = Vec::new();
+        for vector in [[1.0f32, 2.0], [3.0, 4.0]] {
+            fvecs.extend_from_slice(&2i32.to_le_bytes());
+            for value in vector {
+                fvecs.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        assert_eq!(parse_fvecs(&fvecs).unwrap(), vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+        assert!(parse_fvecs(&fvecs[..10]).is_err());
+
+        let header = "{'descr': '<f4', 'fortran_order': False, 'shape': (2, 2), }";
+        let mut npy = b"\x93NUMPY\x01\x00".to_vec();
+        npy.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        npy.extend_from_slice(header.as_bytes());
+        for value in [1.0f32, 2.0, 3.0, 4.0] {
+            npy.extend_from_slice(&value.to_le_bytes());
+        }
+        assert_eq!(parse_npy(&npy).unwrap(), vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+        assert!(parse_npy(&npy[..npy.len() - 4]).is_err());
+
+        let bvecs = [3i32.to_le_bytes().as_slice(), &[0, 128, 255]].concat();
+        assert_eq!(parse_bvecs(&bvecs).unwrap(), vec![vec![0.0, 128.0, 255.0]]);
+
+        let header = "{'descr': '<i8', 'fortran_order': False, 'shape': (2,), }";
+        let mut ids = b"\x93NUMPY\x01\x00".to_vec();
+        ids.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        ids.extend_from_slice(header.as_bytes());
+        for id in [7i64, 42] {
+            ids.extend_from_slice(&id.to_le_bytes());
+        }
+        assert_eq!(parse_npy_ids(&ids).unwrap(), vec!["7", "42"]);
+    }
+
+    #[test]
+    fn test_resolve_within() {
+        let dir = std::env::temp_dir().join(format!("whitematter-import-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub/keys.txt"), "a\n").unwrap();
+        let outside = dir.with_extension("txt");
+        fs::write(&outside, "secret").unwrap();
+
+        let resolved = resolve_within(&dir, Path::new("sub/keys.txt")).unwrap();
+        assert_eq!(resolved, dir.join("sub/keys.txt").canonicalize().unwrap());
+        assert!(resolve_within(&dir, &dir.join("sub/keys.txt")).is_ok());
+
+        assert!(resolve_within(&dir, &outside).is_err());
+        assert!(resolve_within(&dir, Path::new("../").join(outside.file_name().unwrap()).as_path()).is_err());
+        assert!(resolve_within(&dir, Path::new("missing.npy")).is_err());
+    }
+}
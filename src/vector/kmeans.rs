@@ -0,0 +1,108 @@
+use anyhow::{Result, anyhow};
+use rand::Rng;
+use rand::seq::SliceRandom;
+use usearch::MetricKind;
+use crate::vector::chunks::distance;
+
+pub struct KMeans {
+    pub centroids: Vec<Vec<f32>>,
+}
+
+impl KMeans {
+    /// Trains `k` centroids on `vectors` with k-means++ seeding followed by
+    /// up to `iterations` rounds of Lloyd's algorithm.
+    pub fn train(metric: MetricKind, vectors: &[Vec<f32>], k: usize, iterations: usize) -> Result<Self> {
+        if k == 0 || k > vectors.len() {
+            return Err(anyhow!("Cannot form {} clusters from {} vectors", k, vectors.len()));
+        }
+        let mut rng = rand::thread_rng();
+        let mut centroids = vec![vectors.choose(&mut rng).expect("vectors is not empty").clone()];
+        // Seed each further centroid with probability proportional to its
+        // squared distance from the closest centroid picked so far
+        let mut closest: Vec<f32> = vectors.iter().map(|_| f32::INFINITY).collect();
+        while centroids.len() < k {
+            let latest = centroids.last().expect("centroids is not empty");
+            for (vector, best) in vectors.iter().zip(closest.iter_mut()) {
+                *best = best.min(distance(metric, vector, latest)?.max(0.0));
+            }
+            let total: f32 = closest.iter().map(|d| d * d).sum();
+            let next = if total > 0.0 {
+                let mut target = rng.gen::<f32>() * total;
+                closest.iter().position(|d| {
+                    target -= d * d;
+                    target <= 0.0
+                }).unwrap_or(vectors.len() - 1)
+            } else {
+                rng.gen_range(0..vectors.len())
+            };
+            centroids.push(vectors[next].clone());
+        }
+
+        let mut model = Self { centroids };
+        let mut assignments = vec![usize::MAX; vectors.len()];
+        for _ in 0..iterations {
+            let mut changed = false;
+            for (vector, assignment) in vectors.iter().zip(assignments.iter_mut()) {
+                let (cluster, _) = model.assign(metric, vector)?;
+                changed |= cluster != *assignment;
+                *assignment = cluster;
+            }
+            if !changed {
+                break;
+            }
+            model.update(vectors, &assignments);
+        }
+        Ok(model)
+    }
+
+    /// Returns the closest centroid to `vector` and its distance.
+    pub fn assign(&self, metric: MetricKind, vector: &[f32]) -> Result<(usize, f32)> {
+        let mut best = (0, f32::INFINITY);
+        for (i, centroid) in self.centroids.iter().enumerate() {
+            let d = distance(metric, vector, centroid)?;
+            if d < best.1 {
+                best = (i, d);
+            }
+        }
+        Ok(best)
+    }
+
+    /// Moves every centroid to the mean of its members; empty clusters keep
+    /// their previous centroid.
+    fn update(&mut self, vectors: &[Vec<f32>], assignments: &[usize]) {
+        let dimensions = vectors[0].len();
+        let mut sums = vec![vec![0.0f32; dimensions]; self.centroids.len()];
+        let mut counts = vec![0usize; self.centroids.len()];
+        for (vector, &cluster) in vectors.iter().zip(assignments) {
+            counts[cluster] += 1;
+            for (sum, value) in sums[cluster].iter_mut().zip(vector) {
+                *sum += value;
+            }
+        }
+        for ((centroid, sum), count) in self.centroids.iter_mut().zip(sums).zip(counts) {
+            if count > 0 {
+                *centroid = sum.into_iter().map(|value| value / count as f32).collect();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kmeans() {
+        let vectors = vec![
+            vec![0.0, 0.0], vec![0.1, 0.0], vec![0.0, 0.1],
+            vec![10.0, 10.0], vec![10.1, 10.0], vec![10.0, 10.1],
+        ];
+        let model = KMeans::train(MetricKind::L2sq, &vectors, 2, 10).unwrap();
+        let (near_origin, _) = model.assign(MetricKind::L2sq, &[0.05, 0.05]).unwrap();
+        let (far, _) = model.assign(MetricKind::L2sq, &[9.9, 9.9]).unwrap();
+        assert_ne!(near_origin, far);
+        assert_eq!(model.assign(MetricKind::L2sq, &vectors[1]).unwrap().0, near_origin);
+
+        assert!(KMeans::train(MetricKind::L2sq, &vectors, 7, 10).is_err());
+    }
+}
@@ -0,0 +1,78 @@
+use anyhow::{Result, anyhow};
+use usearch::MetricKind;
+use crate::vector::chunks::distance;
+
+/// Maximal marginal relevance reranking parameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MmrOptions {
+    /// Weight of novelty against relevance: 0 ranks purely by relevance,
+    /// 1 purely by distance from the results already picked.
+    pub diversity: f32,
+    /// Candidates retrieved before reranking; defaults to 4 × k.
+    pub fetch_k: Option<usize>,
+}
+
+impl MmrOptions {
+    pub fn new(diversity: f32, fetch_k: Option<usize>) -> Result<Self> {
+        if !(0.0..=1.0).contains(&diversity) {
+            return Err(anyhow!("diversity must be between 0 and 1"));
+        }
+        Ok(Self { diversity, fetch_k })
+    }
+
+    pub fn fetch_k(&self, k: usize) -> usize {
+        self.fetch_k.unwrap_or(k * 4).max(k)
+    }
+}
+
+/// Greedily picks `k` of `candidates`, each time taking the one that best
+/// balances closeness to `query` against closeness to those already picked.
+/// Returns candidate indices in pick order.
+pub fn mmr(metric: MetricKind, query: &[f32], candidates: &[Vec<f32>], k: usize, diversity: f32) -> Result<Vec<usize>> {
+    let relevance = candidates.iter()
+        .map(|candidate| distance(metric, query, candidate).map(|d| -d))
+        .collect::<Result<Vec<f32>>>()?;
+    // Highest similarity of each candidate to any picked result so far
+    let mut redundancy = vec![f32::NEG_INFINITY; candidates.len()];
+    let mut picked = Vec::with_capacity(k.min(candidates.len()));
+    let mut remaining: Vec<usize> = (0..candidates.len()).collect();
+
+    while picked.len() < k && !remaining.is_empty() {
+        let (position, &best) = remaining.iter()
+            .enumerate()
+            .max_by(|(_, &a), (_, &b)| {
+                let score = |i: usize| {
+                    let novelty = if redundancy[i].is_finite() { redundancy[i] } else { 0.0 };
+                    (1.0 - diversity) * relevance[i] - diversity * novelty
+                };
+                score(a).total_cmp(&score(b))
+            })
+            .expect("remaining is not empty");
+        remaining.swap_remove(position);
+        for &i in &remaining {
+            let similarity = -distance(metric, &candidates[i], &candidates[best])?;
+            redundancy[i] = redundancy[i].max(similarity);
+        }
+        picked.push(best);
+    }
+    Ok(picked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mmr() {
+        let query = [1.0, 0.0];
+        let candidates = vec![vec![1.0, 0.0], vec![0.99, 0.01], vec![0.7, 0.7]];
+
+        // Without diversity the near-duplicate comes second
+        assert_eq!(mmr(MetricKind::Cos, &query, &candidates, 2, 0.0).unwrap(), vec![0, 1]);
+        // With diversity the distinct candidate is preferred over it
+        assert_eq!(mmr(MetricKind::Cos, &query, &candidates, 2, 0.7).unwrap(), vec![0, 2]);
+
+        assert!(MmrOptions::new(1.5, None).is_err());
+        assert_eq!(MmrOptions::new(0.5, None).unwrap().fetch_k(10), 40);
+    }
+}
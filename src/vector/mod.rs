@@ -1,3 +1,19 @@
 mod usearch_wrapper;
+mod config;
+mod oplog;
+mod filter;
+mod chunks;
+pub mod io;
+mod mmr;
+mod kmeans;
+pub mod geo;
+mod transform;
 
-pub use usearch_wrapper::UsearchWrapper;
\ No newline at end of file
+pub use usearch_wrapper::{UsearchWrapper, collect_range};
+pub use config::{VectorConfig, DEFAULT_VECTOR_FIELD, validate_field_name, unpack_bits, parse_metric, parse_scalar, metric_name, scalar_name};
+pub use oplog::{VectorLog, VectorOp};
+pub use filter::{FilterExpr, json_path};
+pub use chunks::{Chunk, ChunkAggregation, ChunkMatch, closest_chunks, supports_chunks};
+pub use mmr::{MmrOptions, mmr};
+pub use kmeans::KMeans;
+pub use transform::{Pca, VectorTransform, apply_transforms, transformed_dimensions};
@@ -0,0 +1,142 @@
+use std::sync::Arc;
+use crate::core::RocksDBWrapper;
+use anyhow::{Result, Context, anyhow};
+
+const ENTRY_PREFIX: &[u8] = b"\x00vlog:e:";
+const NEXT_SEQ_KEY: &[u8] = b"\x00vlog:next";
+
+const TAG_ADD: u8 = 1;
+const TAG_REMOVE: u8 = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VectorOp {
+    /// Stores `vector` under `id` in `field`, replacing any previous vector.
+    Add { field: String, id: u64, vector: Vec<f32> },
+    Remove { field: String, id: u64 },
+}
+
+impl VectorOp {
+    pub fn field(&self) -> &str {
+        match self {
+            VectorOp::Add { field, .. } | VectorOp::Remove { field, .. } => field,
+        }
+    }
+}
+
+/// Write-ahead log of vector index mutations. Every change is appended here
+/// before it is applied to the in-memory index, so operations made after the
+/// last saved index file can be replayed after a crash.
+///
+/// Callers must serialize appends; the namespace holds its vector lock.
+pub struct VectorLog {
+    db: Arc<RocksDBWrapper>,
+}
+
+impl VectorLog {
+    pub fn new(db: Arc<RocksDBWrapper>) -> Self {
+        Self { db }
+    }
+
+    pub fn append(&self, op: &VectorOp) -> Result<u64> {
+        let seq = self.last_seq()? + 1;
+        let mut tx = self.db.transaction();
+        tx.put(entry_key(seq), encode(op)).context("Failed to append vector log entry")?;
+        tx.put(NEXT_SEQ_KEY, seq.to_be_bytes()).context("Failed to store vector log sequence")?;
+        tx.commit().context("Failed to commit vector log entry")?;
+        Ok(seq)
+    }
+
+    /// Sequence number of the most recent entry, or 0 if nothing was logged.
+    pub fn last_seq(&self) -> Result<u64> {
+        match self.db.get(NEXT_SEQ_KEY)? {
+            Some(bytes) => decode_u64(&bytes),
+            None => Ok(0),
+        }
+    }
+
+    pub fn entries_after(&self, seq: u64) -> Result<Vec<(u64, VectorOp)>> {
+        let mut entries = Vec::new();
+        for (key, value) in self.db.scan_prefix(ENTRY_PREFIX)? {
+            let entry_seq = decode_u64(&key[ENTRY_PREFIX.len()..])?;
+            if entry_seq > seq {
+                entries.push((entry_seq, decode(&value)?));
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Drops every entry up to and including `seq`, once they are captured
+    /// by a saved index file.
+    pub fn truncate(&self, seq: u64) -> Result<()> {
+        let mut tx = self.db.transaction();
+        for (key, _) in self.db.scan_prefix(ENTRY_PREFIX)? {
+            if decode_u64(&key[ENTRY_PREFIX.len()..])? <= seq {
+                tx.delete(key).context("Failed to delete vector log entry")?;
+            }
+        }
+        tx.commit().context("Failed to commit vector log truncation")?;
+        Ok(())
+    }
+}
+
+fn entry_key(seq: u64) -> Vec<u8> {
+    [ENTRY_PREFIX, &seq.to_be_bytes()[..]].concat()
+}
+
+fn encode(op: &VectorOp) -> Vec<u8> {
+    let (tag, field, id, vector) = match op {
+        VectorOp::Add { field, id, vector } => (TAG_ADD, field, id, vector.as_slice()),
+        VectorOp::Remove { field, id } => (TAG_REMOVE, field, id, &[][..]),
+    };
+    let mut bytes = Vec::with_capacity(10 + field.len() + vector.len() * 4);
+    bytes.push(tag);
+    bytes.extend_from_slice(&id.to_be_bytes());
+    bytes.push(field.len() as u8);
+    bytes.extend_from_slice(field.as_bytes());
+    for value in vector {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes
+}
+
+fn decode(bytes: &[u8]) -> Result<VectorOp> {
+    if bytes.len() < 10 {
+        return Err(anyhow!("Corrupted vector log entry"));
+    }
+    let id = decode_u64(&bytes[1..9])?;
+    let len = bytes[9] as usize;
+    let field = bytes.get(10..10 + len).ok_or_else(|| anyhow!("Corrupted vector log entry"))?;
+    let field = String::from_utf8_lossy(field).into_owned();
+    let payload = &bytes[10 + len..];
+    match bytes[0] {
+        TAG_ADD => {
+            let vector = payload
+                .chunks_exact(4)
+                .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                .collect();
+            Ok(VectorOp::Add { field, id, vector })
+        }
+        TAG_REMOVE => Ok(VectorOp::Remove { field, id }),
+        tag => Err(anyhow!("Unknown vector log entry tag {}", tag)),
+    }
+}
+
+fn decode_u64(bytes: &[u8]) -> Result<u64> {
+    let bytes: [u8; 8] = bytes.try_into()
+        .map_err(|_| anyhow!("Corrupted vector log sequence"))?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_roundtrip() {
+        let op = VectorOp::Add { field: "title".to_string(), id: 42, vector: vec![0.5, -1.25, 3.0] };
+        assert_eq!(decode(&encode(&op)).unwrap(), op);
+        let op = VectorOp::Remove { field: "body".to_string(), id: 7 };
+        assert_eq!(decode(&encode(&op)).unwrap(), op);
+        assert!(decode(&[TAG_ADD, 0, 0]).is_err());
+    }
+}
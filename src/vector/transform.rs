@@ -0,0 +1,223 @@
+use anyhow::{Result, anyhow};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Rounds of subspace iteration when fitting a PCA.
+const PCA_ITERATIONS: usize = 30;
+
+/// A step applied to vectors on their way into a field's index, and to query
+/// vectors before they are searched. Raw vectors are what gets stored and
+/// logged, so transforms can be changed by rebuilding the index.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum VectorTransform {
+    /// Scales vectors to unit L2 norm; zero vectors are left alone.
+    Normalize,
+    /// Keeps the leading `dimensions` values, which Matryoshka-trained
+    /// embeddings are designed to survive.
+    Truncate { dimensions: usize },
+    /// Projects onto principal components fitted on a sample of vectors.
+    Pca(Pca),
+}
+
+impl VectorTransform {
+    pub fn apply(&self, vector: &[f32]) -> Result<Vec<f32>> {
+        match self {
+            VectorTransform::Normalize => {
+                let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+                if norm == 0.0 {
+                    return Ok(vector.to_vec());
+                }
+                Ok(vector.iter().map(|v| v / norm).collect())
+            }
+            VectorTransform::Truncate { dimensions } => vector.get(..*dimensions)
+                .map(<[f32]>::to_vec)
+                .ok_or_else(|| anyhow!("Cannot truncate a vector of {} dimensions to {}", vector.len(), dimensions)),
+            VectorTransform::Pca(pca) => pca.project(vector),
+        }
+    }
+}
+
+/// Applies `transforms` to `vector` in order.
+pub fn apply_transforms(transforms: &[VectorTransform], vector: &[f32]) -> Result<Vec<f32>> {
+    let mut current = vector.to_vec();
+    for transform in transforms {
+        current = transform.apply(&current)?;
+    }
+    Ok(current)
+}
+
+/// Checks that `transforms` can follow one another and returns the
+/// dimensions they produce, or `None` when they keep the input's.
+pub fn transformed_dimensions(transforms: &[VectorTransform]) -> Result<Option<usize>> {
+    let mut dimensions = None;
+    for transform in transforms {
+        match transform {
+            VectorTransform::Normalize => {}
+            VectorTransform::Truncate { dimensions: kept } => {
+                if *kept == 0 {
+                    return Err(anyhow!("Cannot truncate vectors to 0 dimensions"));
+                }
+                if let Some(current) = dimensions.filter(|d| d < kept) {
+                    return Err(anyhow!("Cannot truncate {} dimensions to {}", current, kept));
+                }
+                dimensions = Some(*kept);
+            }
+            VectorTransform::Pca(pca) => {
+                if let Some(current) = dimensions.filter(|d| *d != pca.mean.len()) {
+                    return Err(anyhow!("PCA expects {} dimensions, got {}", pca.mean.len(), current));
+                }
+                dimensions = Some(pca.components.len());
+            }
+        }
+    }
+    Ok(dimensions)
+}
+
+/// A principal component projection: `components` are orthonormal rows,
+/// strongest first, applied after subtracting `mean`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Pca {
+    pub mean: Vec<f32>,
+    pub components: Vec<Vec<f32>>,
+}
+
+impl Pca {
+    /// Fits the top `dimensions` principal components of `vectors` by
+    /// subspace iteration on their covariance matrix.
+    pub fn fit(vectors: &[Vec<f32>], dimensions: usize) -> Result<Self> {
+        let input = vectors.first().map_or(0, Vec::len);
+        if vectors.len() < 2 {
+            return Err(anyhow!("PCA needs at least 2 vectors, got {}", vectors.len()));
+        }
+        if dimensions == 0 || dimensions > input {
+            return Err(anyhow!("Cannot fit {} components to vectors of {} dimensions", dimensions, input));
+        }
+        if vectors.iter().any(|v| v.len() != input) {
+            return Err(anyhow!("PCA sample vectors differ in length"));
+        }
+
+        let n = vectors.len() as f64;
+        let mut mean = vec![0.0f64; input];
+        for vector in vectors {
+            mean.iter_mut().zip(vector).for_each(|(m, v)| *m += *v as f64 / n);
+        }
+        // Upper triangle first, then mirrored
+        let mut covariance = vec![0.0f64; input * input];
+        let mut centered = vec![0.0f64; input];
+        for vector in vectors {
+            centered.iter_mut().zip(vector).zip(&mean).for_each(|((c, v), m)| *c = *v as f64 - m);
+            for i in 0..input {
+                let row = &mut covariance[i * input..(i + 1) * input];
+                for j in i..input {
+                    row[j] += centered[i] * centered[j];
+                }
+            }
+        }
+        for i in 0..input {
+            for j in i..input {
+                let value = covariance[i * input + j] / n;
+                covariance[i * input + j] = value;
+                covariance[j * input + i] = value;
+            }
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut basis: Vec<Vec<f64>> = (0..dimensions)
+            .map(|_| (0..input).map(|_| rng.gen::<f64>() - 0.5).collect())
+            .collect();
+        orthonormalize(&mut basis);
+        for _ in 0..PCA_ITERATIONS {
+            basis = basis.iter().map(|q| multiply(&covariance, q)).collect();
+            orthonormalize(&mut basis);
+        }
+        // Order by explained variance
+        let mut ranked: Vec<(f64, Vec<f64>)> = basis.into_iter()
+            .map(|q| (dot(&q, &multiply(&covariance, &q)), q))
+            .collect();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        Ok(Self {
+            mean: mean.into_iter().map(|m| m as f32).collect(),
+            components: ranked.into_iter()
+                .map(|(_, q)| q.into_iter().map(|v| v as f32).collect())
+                .collect(),
+        })
+    }
+
+    pub fn project(&self, vector: &[f32]) -> Result<Vec<f32>> {
+        if vector.len() != self.mean.len() {
+            return Err(anyhow!("PCA expects vectors of {} dimensions, got {}", self.mean.len(), vector.len()));
+        }
+        Ok(self.components.iter()
+            .map(|component| {
+                component.iter().zip(vector).zip(&self.mean).map(|((c, v), m)| c * (v - m)).sum()
+            })
+            .collect())
+    }
+}
+
+fn multiply(matrix: &[f64], vector: &[f64]) -> Vec<f64> {
+    matrix.chunks_exact(vector.len()).map(|row| dot(row, vector)).collect()
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Modified Gram-Schmidt. A vector that collapses onto the ones before it
+/// (rank-deficient data) is replaced by a basis direction they don't span.
+fn orthonormalize(basis: &mut [Vec<f64>]) {
+    for i in 0..basis.len() {
+        let (done, rest) = basis.split_at_mut(i);
+        let current = &mut rest[0];
+        for previous in done.iter() {
+            let projection = dot(current, previous);
+            current.iter_mut().zip(previous).for_each(|(c, p)| *c -= projection * p);
+        }
+        let mut norm = dot(current, current).sqrt();
+        let mut axis = 0;
+        while norm < 1e-12 && axis < current.len() {
+            current.iter_mut().enumerate().for_each(|(j, c)| *c = if j == axis { 1.0 } else { 0.0 });
+            for previous in done.iter() {
+                let projection = dot(current, previous);
+                current.iter_mut().zip(previous).for_each(|(c, p)| *c -= projection * p);
+            }
+            norm = dot(current, current).sqrt();
+            axis += 1;
+        }
+        current.iter_mut().for_each(|c| *c /= norm);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transforms() {
+        let normalized = VectorTransform::Normalize.apply(&[3.0, 4.0]).unwrap();
+        assert_eq!(normalized, vec![0.6, 0.8]);
+        let truncated = apply_transforms(&[VectorTransform::Truncate { dimensions: 2 }, VectorTransform::Normalize], &[3.0, 4.0, 12.0]).unwrap();
+        assert_eq!(truncated, vec![0.6, 0.8]);
+        assert!(VectorTransform::Truncate { dimensions: 4 }.apply(&[1.0, 2.0]).is_err());
+
+        // Points spread along the x = y diagonal with a little noise in z
+        let vectors: Vec<Vec<f32>> = (0..50)
+            .map(|i| {
+                let t = i as f32 - 25.0;
+                vec![t, t, if i % 2 == 0 { 0.1 } else { -0.1 }]
+            })
+            .collect();
+        let pca = Pca::fit(&vectors, 1).unwrap();
+        let axis = &pca.components[0];
+        assert!((axis[0].abs() - 0.5f32.sqrt()).abs() < 1e-3);
+        assert!((axis[1].abs() - 0.5f32.sqrt()).abs() < 1e-3);
+        assert!(axis[2].abs() < 1e-3);
+        assert_eq!(pca.project(&[1.0, 1.0, 0.0]).unwrap().len(), 1);
+
+        let pipeline = [VectorTransform::Pca(pca), VectorTransform::Normalize];
+        assert_eq!(transformed_dimensions(&pipeline).unwrap(), Some(1));
+        assert!(transformed_dimensions(&[VectorTransform::Truncate { dimensions: 2 }, pipeline[0].clone()]).is_err());
+    }
+}
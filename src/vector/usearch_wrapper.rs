@@ -1,49 +1,273 @@
-use usearch::{Index, IndexOptions, MetricKind, ScalarKind, new_index};
+use usearch::{Index, IndexOptions, new_index};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, RwLock, RwLockReadGuard};
+use std::thread;
 use anyhow::{Result, Context};
+use crate::vector::VectorConfig;
 
 pub struct UsearchWrapper {
     index: Index,
+    // Growing the index reallocates it, and searches with a per-query
+    // expansion change the index-wide setting, so both run exclusively while
+    // adds and regular searches share the lock
+    lock: RwLock<()>,
+    // File a viewed index is memory-mapped from. Viewed indexes are
+    // read-only, so the first write loads the file into memory
+    viewed: Mutex<Option<String>>,
+    // Slots claimed by adds running under the shared lock, so concurrent
+    // adds can't together overrun the capacity each of them checked
+    claimed: AtomicUsize,
 }
 
+/// Room for `count` adds, held while they run.
+struct Claim<'a> {
+    claimed: &'a AtomicUsize,
+    count: usize,
+    _guard: RwLockReadGuard<'a, ()>,
+}
+
+impl Drop for Claim<'_> {
+    fn drop(&mut self) {
+        self.claimed.fetch_sub(self.count, Ordering::SeqCst);
+    }
+}
+
+/// Capacity reserved by the first add to an empty index.
+const INITIAL_CAPACITY: usize = 1024;
+/// Capacity is multiplied by this factor whenever the index fills up.
+const GROWTH_FACTOR: usize = 2;
+/// Neighbours fetched by the first round of a range search.
+const RANGE_INITIAL_K: usize = 16;
+
 impl UsearchWrapper {
-    pub fn new(dimensions: usize, metric: MetricKind, quantization: ScalarKind) -> Result<Self> {
+    pub fn new(config: &VectorConfig) -> Result<Self> {
         let options = IndexOptions {
-            dimensions,
-            metric,
-            quantization,
-            connectivity: 0, // zero for auto
-            expansion_add: 0, // zero for auto
-            expansion_search: 0, // zero for auto
-            multi: false,
+            dimensions: config.dimensions,
+            metric: config.metric,
+            quantization: config.quantization,
+            connectivity: config.connectivity, // zero for auto
+            expansion_add: config.expansion_add, // zero for auto
+            expansion_search: config.expansion_search, // zero for auto
+            multi: config.multi,
         };
 
         let index = new_index(&options).context("Failed to create new index")?;
-        Ok(Self { index })
+        Ok(Self { index, lock: RwLock::new(()), viewed: Mutex::new(None), claimed: AtomicUsize::new(0) })
     }
 
+    /// Makes room for at least `capacity` vectors, e.g. ahead of a bulk load.
     pub fn reserve(&self, capacity: usize) -> Result<()> {
-        self.index.reserve(capacity).context("Failed to reserve capacity")
+        self.ensure_writable()?;
+        let _guard = self.lock.write().unwrap();
+        if capacity > self.index.capacity() {
+            self.index.reserve(capacity).context("Failed to reserve capacity")?;
+        }
+        Ok(())
     }
 
+    /// Adds `vector` under `id`. In multi-vector indexes this appends to the
+    /// vectors already stored under `id`.
     pub fn add(&self, id: u64, vector: &[f32]) -> Result<()> {
+        self.ensure_writable()?;
+        let _claim = self.claim(1)?;
         self.index.add(id, vector).context("Failed to add vector to index")
     }
 
-    pub fn search(&self, vector: &[f32], k: usize) -> Result<Vec<(u64, f32)>> {
-        let results = self.index.search(vector, k).context("Failed to perform search")?;
+    /// Adds many vectors from all cores, growing the index once up front.
+    /// Vectors already stored under one of the ids are replaced.
+    pub fn add_batch(&self, items: &[(u64, Vec<f32>)]) -> Result<()> {
+        self.ensure_writable()?;
+        for (id, _) in items {
+            if self.contains(*id) {
+                self.remove(*id)?;
+            }
+        }
+        let _claim = self.claim(items.len())?;
+        let threads = thread::available_parallelism().map_or(1, |n| n.get()).min(items.len().max(1));
+        let per_thread = items.len().div_ceil(threads).max(1);
+        thread::scope(|scope| {
+            let workers: Vec<_> = items.chunks(per_thread)
+                .map(|batch| scope.spawn(move || {
+                    batch.iter().try_for_each(|(id, vector)| {
+                        self.index.add(*id, vector).context("Failed to add vector to index")
+                    })
+                }))
+                .collect();
+            for worker in workers {
+                worker.join().map_err(|_| anyhow::anyhow!("Indexing thread panicked"))??;
+            }
+            Ok(())
+        })
+    }
+
+    /// Takes the shared lock with room for `count` more vectors, growing the
+    /// index first if they don't fit next to the vectors being added by
+    /// other threads.
+    fn claim(&self, count: usize) -> Result<Claim<'_>> {
+        loop {
+            {
+                let guard = self.lock.read().unwrap();
+                let claimed = self.claimed.fetch_add(count, Ordering::SeqCst) + count;
+                let claim = Claim { claimed: &self.claimed, count, _guard: guard };
+                if self.index.size() + claimed <= self.index.capacity() {
+                    return Ok(claim);
+                }
+            }
+            self.grow(count)?;
+        }
+    }
+
+    /// Grows the index geometrically when `additional` vectors don't fit, so
+    /// a long run of adds reallocates only a logarithmic number of times.
+    fn grow(&self, additional: usize) -> Result<()> {
+        // No adds are running while the lock is held exclusively
+        let _guard = self.lock.write().unwrap();
+        let needed = self.index.size() + additional;
+        let capacity = self.index.capacity();
+        if needed > capacity {
+            let grown = needed.max(capacity * GROWTH_FACTOR).max(INITIAL_CAPACITY);
+            self.index.reserve(grown).context("Failed to grow index capacity")?;
+        }
+        Ok(())
+    }
+
+    /// Loads a viewed index into memory so it can be modified.
+    fn ensure_writable(&self) -> Result<()> {
+        let mut viewed = self.viewed.lock().unwrap();
+        if let Some(path) = viewed.as_ref() {
+            let _guard = self.lock.write().unwrap();
+            self.index.load(path).context("Failed to load viewed index for writing")?;
+            *viewed = None;
+        }
+        Ok(())
+    }
+
+    /// Removes every vector stored under `id`, returning how many were removed.
+    pub fn remove(&self, id: u64) -> Result<usize> {
+        self.ensure_writable()?;
+        let _guard = self.lock.read().unwrap();
+        self.index.remove(id).context("Failed to remove vector from index")
+    }
+
+    /// Stores `vector` under `id`, dropping whatever was stored there before.
+    pub fn replace(&self, id: u64, vector: &[f32]) -> Result<()> {
+        if self.contains(id) {
+            self.remove(id)?;
+        }
+        self.add(id, vector)
+    }
+
+    pub fn contains(&self, id: u64) -> bool {
+        self.index.contains(id)
+    }
+
+    /// Searches for the `k` nearest vectors. `expansion` overrides the
+    /// index's `expansion_search` for this query only, which swaps the
+    /// index-wide setting under the exclusive lock and so serializes the
+    /// query with every other search and write.
+    pub fn search(&self, vector: &[f32], k: usize, expansion: Option<usize>) -> Result<Vec<(u64, f32)>> {
+        let results = self.with_expansion(expansion, || self.index.search(vector, k))
+            .context("Failed to perform search")?;
         Ok(results.keys.into_iter().zip(results.distances).collect())
     }
 
+    /// Returns every vector within `max_distance` of `vector`, closest first,
+    /// up to `limit`.
+    pub fn range_search(&self, vector: &[f32], max_distance: f32, limit: usize, expansion: Option<usize>) -> Result<Vec<(u64, f32)>> {
+        collect_range(max_distance, limit.min(self.size()), |k| self.search(vector, k, expansion))
+    }
+
+    /// Runs many searches at once, spreading the queries over one thread per
+    /// available core. Results come back in query order.
+    pub fn search_batch(&self, vectors: &[Vec<f32>], k: usize, expansion: Option<usize>) -> Result<Vec<Vec<(u64, f32)>>> {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get()).min(vectors.len().max(1));
+        let per_thread = vectors.len().div_ceil(threads).max(1);
+        self.with_expansion(expansion, || {
+            thread::scope(|scope| {
+                let workers: Vec<_> = vectors.chunks(per_thread)
+                    .map(|queries| scope.spawn(move || {
+                        queries.iter()
+                            .map(|vector| {
+                                let results = self.index.search(vector, k).context("Failed to perform search")?;
+                                Ok(results.keys.into_iter().zip(results.distances).collect())
+                            })
+                            .collect::<Result<Vec<_>>>()
+                    }))
+                    .collect();
+                let mut results = Vec::with_capacity(vectors.len());
+                for worker in workers {
+                    results.extend(worker.join().map_err(|_| anyhow::anyhow!("Search thread panicked"))??);
+                }
+                Ok(results)
+            })
+        })
+    }
+
+    /// Searches only among ids accepted by `filter`. The predicate runs
+    /// during graph traversal, so up to `k` matches are returned even when
+    /// the filter is very selective.
+    pub fn filtered_search<F: Fn(u64) -> bool>(&self, vector: &[f32], k: usize, expansion: Option<usize>, filter: F) -> Result<Vec<(u64, f32)>> {
+        let results = self.with_expansion(expansion, || self.index.filtered_search(vector, k, filter))
+            .context("Failed to perform filtered search")?;
+        Ok(results.keys.into_iter().zip(results.distances).collect())
+    }
+
+    /// Brute-force search over every vector; slow, but exact, which makes it
+    /// the ground truth for measuring the recall of `search`.
+    pub fn exact_search(&self, vector: &[f32], k: usize) -> Result<Vec<(u64, f32)>> {
+        let _guard = self.lock.read().unwrap();
+        let results = self.index.exact_search(vector, k).context("Failed to perform exact search")?;
+        Ok(results.keys.into_iter().zip(results.distances).collect())
+    }
+
+    /// Returns the vectors stored under `id`; several in multi-vector indexes.
+    pub fn get(&self, id: u64) -> Result<Vec<Vec<f32>>> {
+        let _guard = self.lock.read().unwrap();
+        let dimensions = self.index.dimensions();
+        let mut buffer = vec![0.0f32; self.index.count(id) * dimensions];
+        let found = self.index.get(id, &mut buffer).context("Failed to read vector from index")?;
+        buffer.truncate(found * dimensions);
+        Ok(buffer.chunks(dimensions).map(|vector| vector.to_vec()).collect())
+    }
+
+    fn with_expansion<R>(&self, expansion: Option<usize>, search: impl FnOnce() -> R) -> R {
+        match expansion {
+            None => {
+                let _guard = self.lock.read().unwrap();
+                search()
+            }
+            Some(expansion) => {
+                let _guard = self.lock.write().unwrap();
+                let default = self.index.expansion_search();
+                self.index.change_expansion_search(expansion);
+                let result = search();
+                self.index.change_expansion_search(default);
+                result
+            }
+        }
+    }
+
     pub fn save(&self, path: &str) -> Result<()> {
+        let _guard = self.lock.read().unwrap();
         self.index.save(path).context("Failed to save index")
     }
 
     pub fn load(&self,path: &str) -> Result<()> {
-        self.index.load(path).context("Failed to save index")
+        let mut viewed = self.viewed.lock().unwrap();
+        let _guard = self.lock.write().unwrap();
+        self.index.load(path).context("Failed to load index")?;
+        *viewed = None;
+        Ok(())
     }
 
+    /// Memory-maps the index saved at `path`. The file must stay in place
+    /// until the first write, which loads it into memory.
     pub fn view(&self,path: &str) -> Result<()> {
-        self.index.view(path).context("Failed to save index")
+        let mut viewed = self.viewed.lock().unwrap();
+        let _guard = self.lock.write().unwrap();
+        self.index.view(path).context("Failed to view index")?;
+        *viewed = Some(path.to_string());
+        Ok(())
     }
 
     pub fn capacity(&self) -> usize {
@@ -54,6 +278,18 @@ impl UsearchWrapper {
         self.index.connectivity()
     }
 
+    pub fn expansion_add(&self) -> usize {
+        self.index.expansion_add()
+    }
+
+    pub fn expansion_search(&self) -> usize {
+        self.index.expansion_search()
+    }
+
+    pub fn memory_usage(&self) -> usize {
+        self.index.memory_usage()
+    }
+
     pub fn dimensions(&self) -> usize {
         self.index.dimensions()
     }
@@ -61,4 +297,124 @@ impl UsearchWrapper {
     pub fn size(&self) -> usize {
         self.index.size()
     }
-}
\ No newline at end of file
+}
+
+/// Turns a k-nearest-neighbour `search` into a range search: runs it with a
+/// growing `k` until a hit falls outside `max_distance` or fewer than `k`
+/// hits come back, and returns the hits within the bound, up to `limit`.
+/// `limit` must not exceed the number of vectors searched.
+pub fn collect_range<F>(max_distance: f32, limit: usize, mut search: F) -> Result<Vec<(u64, f32)>>
+where
+    F: FnMut(usize) -> Result<Vec<(u64, f32)>>,
+{
+    let mut k = RANGE_INITIAL_K.min(limit);
+    loop {
+        let mut results = search(k)?;
+        let exhausted = results.len() < k || k >= limit;
+        let bounded = results.last().map_or(true, |(_, distance)| *distance > max_distance);
+        if exhausted || bounded {
+            results.retain(|(_, distance)| *distance <= max_distance);
+            results.truncate(limit);
+            return Ok(results);
+        }
+        k = (k * 2).min(limit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use usearch::{MetricKind, ScalarKind};
+
+    fn wrapper() -> UsearchWrapper {
+        UsearchWrapper::new(&VectorConfig::new(2, MetricKind::L2sq, ScalarKind::F32)).unwrap()
+    }
+
+    #[test]
+    fn test_add_grows_capacity() {
+        let index = wrapper();
+        assert_eq!(index.capacity(), 0);
+        index.add(0, &[0.0, 0.0]).unwrap();
+        assert!(index.capacity() >= INITIAL_CAPACITY);
+
+        for id in 1..(INITIAL_CAPACITY as u64 + 500) {
+            index.add(id, &[id as f32, 0.0]).unwrap();
+        }
+        assert_eq!(index.size(), INITIAL_CAPACITY + 500);
+        assert!(index.capacity() >= INITIAL_CAPACITY + 500);
+        assert_eq!(index.search(&[1100.0, 0.0], 1, None).unwrap()[0].0, 1100);
+    }
+
+    #[test]
+    fn test_concurrent_adds_past_capacity() {
+        let index = wrapper();
+        let threads = 8;
+        let per_thread = INITIAL_CAPACITY / 4;
+        thread::scope(|scope| {
+            for t in 0..threads {
+                let index = &index;
+                scope.spawn(move || {
+                    for i in 0..per_thread {
+                        let id = (t * per_thread + i) as u64;
+                        index.add(id, &[id as f32, 1.0]).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(index.size(), threads * per_thread);
+        assert!(index.capacity() >= threads * per_thread);
+    }
+
+    #[test]
+    fn test_reserve() {
+        let index = wrapper();
+        index.reserve(5000).unwrap();
+        assert!(index.capacity() >= 5000);
+        // Reserving less never shrinks the index
+        index.reserve(10).unwrap();
+        assert!(index.capacity() >= 5000);
+
+        let items: Vec<(u64, Vec<f32>)> = (0..3000).map(|id| (id, vec![id as f32, 0.0])).collect();
+        index.add_batch(&items).unwrap();
+        assert_eq!(index.size(), 3000);
+        assert!(index.capacity() >= 5000);
+    }
+
+    #[test]
+    fn test_range_search() {
+        let index = wrapper();
+        for id in 0..100u64 {
+            index.add(id, &[id as f32, 0.0]).unwrap();
+        }
+        // Squared distances: ids 0..=40 are in range, past the first round
+        let hits = index.range_search(&[0.0, 0.0], 1600.0, usize::MAX, None).unwrap();
+        let ids: Vec<u64> = hits.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, (0..=40).collect::<Vec<_>>());
+        assert!(hits.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+
+        assert_eq!(index.range_search(&[0.0, 0.0], 1600.0, 20, None).unwrap().len(), 20);
+        assert_eq!(index.range_search(&[0.0, 0.0], f32::MAX, usize::MAX, None).unwrap().len(), 100);
+        assert!(index.range_search(&[500.0, 0.0], 1.0, usize::MAX, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_collect_range_grows_k() {
+        let distances: Vec<f32> = (0..100).map(|i| i as f32).collect();
+        let mut rounds = Vec::new();
+        let hits = collect_range(40.0, distances.len(), |k| {
+            rounds.push(k);
+            Ok(distances.iter().take(k).enumerate().map(|(id, d)| (id as u64, *d)).collect())
+        }).unwrap();
+        assert_eq!(hits.len(), 41);
+        assert_eq!(rounds, vec![RANGE_INITIAL_K, RANGE_INITIAL_K * 2, RANGE_INITIAL_K * 4]);
+
+        // Stops once the search runs out of vectors
+        let mut rounds = 0;
+        let hits = collect_range(1000.0, 30, |k| {
+            rounds += 1;
+            Ok(distances.iter().take(k.min(30)).enumerate().map(|(id, d)| (id as u64, *d)).collect())
+        }).unwrap();
+        assert_eq!(hits.len(), 30);
+        assert_eq!(rounds, 2);
+    }
+}